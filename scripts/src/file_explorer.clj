@@ -4,6 +4,21 @@
    [cheshire.core :as json]
    [babashka.fs :as fs]))
 
+;; Constants -------------------------------------------------------------------
+
+(def file-alt-actions
+  [{:key "ctrl+enter"
+    :title "Reveal in file manager"
+    :shell "xdg-open \"$(dirname \"$__COMMAND_VALUE\")\""
+    :action "Exit"}
+   {:key "alt+enter"
+    :title "Copy path"
+    :shell "printf '%s' \"$__COMMAND_VALUE\""
+    :action "Print"}
+   {:title "Delete"
+    :shell "bb ./scripts/src/file_explorer.clj confirm-delete \"$__COMMAND_VALUE\""
+    :action "Next"}])
+
 ;; Functions -------------------------------------------------------------------
 
 (defn list-dir-data-edn [path]
@@ -21,8 +36,10 @@
                                 :action "Next"}
                                {:title (str title)
                                 :value (str cur)
+                                :shell "xdg-open \"$__COMMAND_VALUE\""
                                 :icon "File"
-                                :action "Exit"})
+                                :action "Exit"
+                                :alt_actions file-alt-actions})
                         key (if dir? :dirs :files)]
                     (update acc key conj item)))
                 {:dirs []
@@ -33,6 +50,16 @@
      :shell (str "bb ./scripts/src/file_explorer.clj " path)
      :items items}))
 
+;; Confirmation menu before a file is deleted, going back keeps the file
+(defn confirm-delete-data-edn [path]
+  {:value (str "Delete " (fs/file-name path) "?")
+   :items [{:title (str "Delete " (fs/file-name path))
+            :subtitle "Go back to keep the file"
+            :value (str path)
+            :shell "rm -- \"$__COMMAND_VALUE\""
+            :icon "File"
+            :action "Exit"}]})
+
 ;; Commands --------------------------------------------------------------------
 
 (defn list-dir-cmd [path]
@@ -40,6 +67,11 @@
       (json/generate-string)
       (doto println)))
 
+(defn confirm-delete-cmd [path]
+  (-> (confirm-delete-data-edn path)
+      (json/generate-string)
+      (doto println)))
+
 (comment
   (list-dir-data-edn "~")
   nil)
@@ -49,6 +81,7 @@
 (defn -main [& args]
   ;; (Thread/sleep 5000)
   (match (vec args)
+         ["confirm-delete" path] (confirm-delete-cmd path)
          [path] (list-dir-cmd path)
          :else (System/exit 1)))
 
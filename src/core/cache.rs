@@ -0,0 +1,160 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::commands::{CachePolicy, Command};
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    command: Command,
+    // Seconds since the unix epoch
+    stored_at: u64,
+}
+
+/// Parsed results of `next` commands by their `Command::cache_key`.
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    entries: HashMap<String, CacheEntry>,
+    // Where persisted results are stored, results stay in memory without one
+    dir: Option<PathBuf>,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl ResultCache {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        ResultCache {
+            entries: HashMap::new(),
+            dir,
+        }
+    }
+
+    /// Location of persisted results in the XDG cache directory.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("iced_prompt").join("results"))
+    }
+
+    /// The cached result for `key`, unless it is older than the `ttl` of `policy`.
+    pub fn get(&mut self, key: &str, policy: &CachePolicy) -> Option<Command> {
+        if !self.entries.contains_key(key) && policy.persist {
+            if let Some(entry) = self.read(key) {
+                self.entries.insert(key.to_string(), entry);
+            }
+        }
+
+        let entry = self.entries.get(key)?;
+        let age = now().saturating_sub(entry.stored_at);
+        match policy.ttl {
+            Some(ttl) if age >= ttl => None,
+            _ => Some(entry.command.clone()),
+        }
+    }
+
+    pub fn insert(&mut self, key: String, command: Command, policy: &CachePolicy) {
+        let entry = CacheEntry {
+            command,
+            stored_at: now(),
+        };
+        if policy.persist {
+            self.write(&key, &entry);
+        }
+        self.entries.insert(key, entry);
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{:016x}.json", hasher.finish())))
+    }
+
+    fn read(&self, key: &str) -> Option<CacheEntry> {
+        let json_string = fs::read_to_string(self.path(key)?).ok()?;
+        serde_json::from_str(&json_string).ok()
+    }
+
+    // The cache only speeds things up, so failing to write it is not fatal
+    fn write(&self, key: &str, entry: &CacheEntry) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json_string = serde_json::to_string(entry)?;
+                fs::write(&path, json_string)
+            });
+        if let Err(err) = result {
+            eprintln!("Failed to cache the result at {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResultCache;
+    use crate::core::commands::{CachePolicy, Command};
+    use crate::s;
+    use crate::utils::temp_dir::TempDir;
+
+    fn command(value: &str) -> Command {
+        Command {
+            value: s!(value),
+            ..Command::default()
+        }
+    }
+
+    #[test]
+    fn expires_results_after_their_ttl() {
+        let mut cache = ResultCache::default();
+        let fresh = CachePolicy {
+            ttl: Some(60),
+            ..CachePolicy::default()
+        };
+        let expired = CachePolicy {
+            ttl: Some(0),
+            ..CachePolicy::default()
+        };
+
+        cache.insert(s!("ls"), command("files"), &fresh);
+
+        assert_eq!(cache.get("ls", &fresh), Some(command("files")));
+        assert_eq!(
+            cache.get("ls", &CachePolicy::default()),
+            Some(command("files"))
+        );
+        assert_eq!(cache.get("ls", &expired), None);
+        assert_eq!(cache.get("docker ps", &fresh), None);
+    }
+
+    #[test]
+    fn persists_results_on_disk() {
+        let temp_dir = TempDir::new("result_cache");
+        let dir = temp_dir.path().join("cache");
+        let persist = CachePolicy {
+            persist: true,
+            ..CachePolicy::default()
+        };
+
+        ResultCache::new(Some(dir.clone())).insert(s!("ls"), command("files"), &persist);
+
+        let mut cache = ResultCache::new(Some(dir));
+        assert_eq!(cache.get("ls", &CachePolicy::default()), None);
+        let cached = cache.get("ls", &persist).unwrap();
+        assert_eq!(cached.value, "files");
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::core::commands::{Command, KNOWN_ICONS};
+use crate::core::template;
+use crate::core::theme::parse_hex_color;
+
+// Constants -------------------------------------------------------------------
+
+const COMMAND_KEYS: &[&str] = &[
+    "id",
+    "title",
+    "value",
+    "shell",
+    "kind",
+    "provider",
+    "cwd",
+    "icon",
+    "section",
+    "subtitle",
+    "accessory",
+    "badges",
+    "image",
+    "layout",
+    "columns",
+    "action",
+    "cache",
+    "refresh_interval",
+    "items",
+    "alt_actions",
+    "include",
+];
+
+const ALT_ACTION_KEYS: &[&str] = &["key", "title", "shell", "kind", "action"];
+
+const KIND_KEYS: &[&str] = &["shell"];
+
+// Palette colors that badges can refer to by name
+const BADGE_COLORS: &[&str] = &["primary", "secondary", "surface", "error"];
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // "line 3, column 7" for errors in JSON text, otherwise the path of the field: "root.items[0].action"
+    pub location: String,
+    pub message: String,
+}
+
+/// Validates the commands trees of a file and of the files it includes, each on its own
+/// but with ids unique across all of them.
+#[derive(Debug, Default)]
+pub struct Checker {
+    // File and path of the first use of every id
+    ids: HashMap<String, (String, String)>,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.location, self.message)
+    }
+}
+
+impl Diagnostic {
+    fn error(location: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            location: location.to_string(),
+            message,
+        }
+    }
+
+    fn warning(location: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            location: location.to_string(),
+            message,
+        }
+    }
+
+    fn from_json_error(err: serde_json::Error) -> Self {
+        let location = format!("line {}, column {}", err.line(), err.column());
+        let message = err.to_string();
+        let suffix = format!(" at line {} column {}", err.line(), err.column());
+        let message = message.strip_suffix(&suffix).unwrap_or(&message);
+        Diagnostic::error(&location, message.to_string())
+    }
+}
+
+impl Checker {
+    /// Validate the commands tree of `file` in its JSON form.
+    /// Syntax and type errors stop the check and are reported with their line and column,
+    /// everything else is collected.
+    pub fn check(&mut self, file: &str, json_str: &str) -> Vec<Diagnostic> {
+        let value: Value = match serde_json::from_str(json_str) {
+            Ok(value) => value,
+            Err(err) => return vec![Diagnostic::from_json_error(err)],
+        };
+        let parsed = match value {
+            Value::Array(_) => serde_json::from_str::<Vec<Command>>(json_str).map(|_| ()),
+            _ => serde_json::from_str::<Command>(json_str).map(|_| ()),
+        };
+        match parsed {
+            Ok(()) => self.check_value(file, &value),
+            Err(err) => vec![Diagnostic::from_json_error(err)],
+        }
+    }
+
+    /// Validate the commands tree of `file` as read from it, e.g. from TOML.
+    /// Included files can hold a list of commands.
+    /// Type errors stop the check and are reported at their command, everything else is collected.
+    pub fn check_value(&mut self, file: &str, value: &Value) -> Vec<Diagnostic> {
+        let commands: Vec<(String, &Value)> = match value {
+            Value::Array(commands) => commands
+                .iter()
+                .enumerate()
+                .map(|(index, command)| (format!("root[{}]", index), command))
+                .collect(),
+            command => vec![("root".to_string(), command)],
+        };
+
+        let mut diagnostics = vec![];
+        for (path, command) in &commands {
+            check_types(command, path, &mut diagnostics);
+        }
+        if !diagnostics.is_empty() {
+            return diagnostics;
+        }
+
+        for (path, command) in &commands {
+            self.check_command(file, command, path, &mut diagnostics);
+        }
+        diagnostics
+    }
+
+    fn check_command(
+        &mut self,
+        file: &str,
+        value: &Value,
+        path: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(fields) = value.as_object() else {
+            return;
+        };
+
+        check_unknown_keys(fields, COMMAND_KEYS, path, diagnostics);
+        check_kind(fields, path, diagnostics);
+
+        if let Some(id) = fields.get("id").and_then(Value::as_str) {
+            match self.ids.get(id) {
+                Some((first_file, first_path)) => {
+                    let first_use = match first_file == file {
+                        true => first_path.clone(),
+                        false => format!("{}: {}", first_file, first_path),
+                    };
+                    diagnostics.push(Diagnostic::error(
+                        &field_path(path, "id"),
+                        format!("Duplicate id `{}`, first used at {}", id, first_use),
+                    ))
+                }
+                None => {
+                    self.ids
+                        .insert(id.to_string(), (file.to_string(), field_path(path, "id")));
+                }
+            }
+        }
+
+        check_fields(fields, path, diagnostics);
+
+        if let Some(items) = fields.get("items").and_then(Value::as_array) {
+            for (index, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", field_path(path, "items"), index);
+                self.check_command(file, item, &item_path, diagnostics);
+            }
+        }
+    }
+}
+
+// Report type errors at the innermost command that fails to deserialize
+fn check_types(value: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let items = value.get("items").and_then(Value::as_array);
+    if let Some(items) = items {
+        for (index, item) in items.iter().enumerate() {
+            let item_path = format!("{}[{}]", field_path(path, "items"), index);
+            check_types(item, &item_path, diagnostics);
+        }
+    }
+
+    let mut command = value.clone();
+    if let (Some(fields), Some(_)) = (command.as_object_mut(), items) {
+        fields.remove("items");
+    }
+    if let Err(err) = serde_json::from_value::<Command>(command) {
+        diagnostics.push(Diagnostic::error(path, err.to_string()));
+    }
+}
+
+// Check the icon, badges and alternative actions of a command
+fn check_fields(fields: &Map<String, Value>, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(icon) = fields.get("icon").and_then(Value::as_str) {
+        // Templated icons are only known once they are expanded
+        if !KNOWN_ICONS.contains(&icon) && !template::is_template(icon) {
+            diagnostics.push(Diagnostic::warning(
+                &field_path(path, "icon"),
+                format!(
+                    "Unknown icon `{}`, expected one of: {}",
+                    icon,
+                    KNOWN_ICONS.join(", ")
+                ),
+            ));
+        }
+    }
+
+    if let Some(badges) = fields.get("badges").and_then(Value::as_array) {
+        for (index, badge) in badges.iter().enumerate() {
+            let color = badge.get("color").and_then(Value::as_str);
+            if let Some(color) = color.filter(|c| !is_badge_color(c)) {
+                diagnostics.push(Diagnostic::warning(
+                    &format!("{}[{}].color", field_path(path, "badges"), index),
+                    format!(
+                        "Unknown color `{}`, expected one of: {} or a hex color",
+                        color,
+                        BADGE_COLORS.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let Some(alt_actions) = fields.get("alt_actions").and_then(Value::as_array) {
+        for (index, alt_action) in alt_actions.iter().enumerate() {
+            let alt_path = format!("{}[{}]", field_path(path, "alt_actions"), index);
+            if let Some(alt_fields) = alt_action.as_object() {
+                check_unknown_keys(alt_fields, ALT_ACTION_KEYS, &alt_path, diagnostics);
+                check_kind(alt_fields, &alt_path, diagnostics);
+            }
+        }
+    }
+}
+
+// A `next` action shows the output of its shell command or provider, so it needs one
+fn check_kind(fields: &Map<String, Value>, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let kind = fields.get("shell").or_else(|| fields.get("kind"));
+    if let Some(Value::Object(kind_fields)) = kind {
+        let key = if fields.contains_key("shell") {
+            "shell"
+        } else {
+            "kind"
+        };
+        check_unknown_keys(kind_fields, KIND_KEYS, &field_path(path, key), diagnostics);
+    }
+
+    let has_shell = match kind {
+        Some(Value::String(_)) => true,
+        Some(Value::Object(kind_fields)) => kind_fields.contains_key("shell"),
+        _ => false,
+    } || fields.contains_key("provider");
+    let action = fields.get("action").and_then(Value::as_str);
+    if !has_shell && action.is_some_and(|action| action.eq_ignore_ascii_case("next")) {
+        diagnostics.push(Diagnostic::error(
+            path,
+            "A `next` action requires a `shell` command or a `provider`".to_string(),
+        ));
+    }
+}
+
+fn check_unknown_keys(
+    fields: &Map<String, Value>,
+    known_keys: &[&str],
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for key in fields.keys() {
+        if !known_keys.contains(&key.as_str()) {
+            diagnostics.push(Diagnostic::warning(
+                &field_path(path, key),
+                format!(
+                    "Unknown key `{}`, expected one of: {}",
+                    key,
+                    known_keys.join(", ")
+                ),
+            ));
+        }
+    }
+}
+
+fn is_badge_color(color: &str) -> bool {
+    BADGE_COLORS.contains(&color) || parse_hex_color(color).is_some()
+}
+
+fn field_path(path: &str, key: &str) -> String {
+    format!("{}.{}", path, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{Checker, Diagnostic, Severity};
+
+    fn check(json_str: &str) -> Vec<Diagnostic> {
+        Checker::default().check("commands.json", json_str)
+    }
+
+    fn check_str(json_str: &str) -> Vec<Diagnostic> {
+        let value: Value = serde_json::from_str(json_str).unwrap();
+        Checker::default().check_value("commands.json", &value)
+    }
+
+    fn locations(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.location.as_str()).collect()
+    }
+
+    #[test]
+    fn accepts_valid_trees() {
+        let data =
+            include_str!("../../data/user_friendly_simple.json").replace("decription", "title");
+
+        assert_eq!(check_str(&data), vec![]);
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_position() {
+        let diagnostics = check("{\n  \"value\": \"Commands\",\n  \"items\": [\n}");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].location, "line 4, column 1");
+
+        let type_error = check(r#"{ "value": 5 }"#);
+        assert_eq!(type_error[0].location, "line 1, column 12");
+    }
+
+    #[test]
+    fn reports_type_errors_at_their_command() {
+        let data = r#"{ "value": "Commands", "items": [{ "value": "a" }, { "value": 5 }] }"#;
+
+        let diagnostics = check_str(data);
+
+        assert_eq!(locations(&diagnostics), vec!["root.items[1]"]);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].message.contains("invalid type"));
+    }
+
+    #[test]
+    fn accepts_next_actions_with_a_provider() {
+        let data = r#"{ "value": "Commands", "items": [
+            { "value": "Processes", "provider": "processes", "action": "next" },
+            { "value": "Nothing", "action": "next" }
+        ] }"#;
+
+        let diagnostics = check_str(data);
+
+        assert_eq!(locations(&diagnostics), vec!["root.items[1]"]);
+    }
+
+    #[test]
+    fn reports_legacy_kind_objects() {
+        let data = include_str!("../../data/system_types_simple.json");
+
+        let diagnostics = check_str(data);
+
+        assert_eq!(
+            locations(&diagnostics),
+            vec!["root.items[0].kind.SyncShellCommand", "root.items[0]"]
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn checks_included_files_on_their_own() {
+        let mut checker = Checker::default();
+
+        let root = checker.check(
+            "commands.json",
+            r#"{ "value": "Commands", "include": "topics.json", "items": [{ "id": "a", "value": "a" }] }"#,
+        );
+        assert_eq!(root, vec![]);
+
+        let included = checker.check(
+            "topics.json",
+            r#"[{ "id": "a", "value": "b" }, { "value": "c", "action": "next" }]"#,
+        );
+        assert_eq!(locations(&included), vec!["root[0].id", "root[1]"]);
+        assert!(included[0]
+            .message
+            .contains("first used at commands.json: root.items[0].id"));
+    }
+
+    #[test]
+    fn reports_unknown_keys_icons_and_duplicate_ids() {
+        let data = r#"{
+    "value": "Commands",
+    "items": [
+        { "id": "a", "value": "a", "decription": "typo", "icon": "Folder", "badges": [{ "text": "x", "color": "pink" }] },
+        { "id": "a", "value": "b", "alt_actions": [{ "value": "c" }] },
+        { "value": "d", "icon": "${ICON:-File}" }
+    ]
+}"#;
+
+        let diagnostics = check_str(data);
+
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                "root.items[0].decription",
+                "root.items[0].icon",
+                "root.items[0].badges[0].color",
+                "root.items[1].id",
+                "root.items[1].alt_actions[0].value",
+            ]
+        );
+        assert!(diagnostics[3].message.contains("root.items[0].id"));
+    }
+}
@@ -1,20 +1,49 @@
 use std::collections::HashMap;
-use std::process;
+
+use async_std::process::{self, Stdio};
 
 use levenshtein::levenshtein;
 use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
-use serde::Deserialize;
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::{self};
 
 use uuid::Uuid;
 
+use crate::core::context::ExecutionContext;
+use crate::core::keymap::KeyChord;
+use crate::core::providers::{self, Provider};
+use crate::core::template;
+
 // Constants -------------------------------------------------------------------
 
 pub const SIMPLE_CMD_HEIGHT: f32 = 28.;
 
+// Columns of a grid without explicit `columns`
+pub const DEFAULT_GRID_COLUMNS: usize = 5;
+
+// Icons that can be shown in front of a command
+pub const KNOWN_ICONS: &[&str] = &["Directory", "File"];
+
 // Types -----------------------------------------------------------------------
 
+/// Layout metrics of the rows in a list, used to compute scroll offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RowMetrics {
+    pub row_height: f32,
+    // Space between two rows
+    pub spacing: f32,
+    // Vertical padding of the row button
+    pub padding: f32,
+    // Height of the heading above the first item of a section
+    pub header_height: f32,
+    // Height of the second line of rows with a subtitle
+    pub subtitle_height: f32,
+    // Height of the content of a grid cell
+    pub cell_height: f32,
+}
+
 #[derive(Deserialize, Default, Debug, Clone, Eq, PartialEq)]
 pub struct ShellProperties {
     pub command: String,
@@ -42,7 +71,8 @@ pub struct Items<T> {
     pub order: Vec<Uuid>,
 }
 
-#[derive(Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(rename_all(serialize = "lowercase"))]
 pub enum ActionKind {
     #[default]
     #[serde(alias = "exit")]
@@ -58,19 +88,122 @@ pub enum ActionKind {
     Print,
 }
 
-#[derive(Deserialize, Default, Debug, Clone, Eq, PartialEq)]
-pub struct Command {
+// How the items of a command are laid out
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    List,
+    // Wrapping grid for icon-heavy lists, without section headers
+    Grid,
+}
+
+// How the parsed output of a `next` command is reused when entering it again
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CachePolicy {
+    // Seconds a result is shown instantly, forever when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    // Cache a result per `value` instead of per shell command
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub by_value: bool,
+    // Keep results on disk, so they survive restarts
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub persist: bool,
+}
+
+// Colored label shown after the title of a command
+// Deserializes from its text or from an object: "wip", {"text": "wip", "color": "error"}
+#[derive(Serialize, Default, Debug, Clone, Eq, PartialEq)]
+pub struct Badge {
+    pub text: String,
+    // Name of a palette color (primary, secondary, surface, error) or a hex color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+// Secondary action of a command, triggered by its `key` or from the action menu
+// It runs with the `value` of the command it belongs to
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Eq, PartialEq)]
+pub struct AltAction {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<KeyChord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        default,
+        alias = "shell",
+        rename(serialize = "shell"),
+        deserialize_with = "deserialize_kind",
+        serialize_with = "serialize_kind",
+        skip_serializing_if = "CommandKind::is_initial"
+    )]
+    pub kind: CommandKind,
     #[serde(default)]
+    pub action: ActionKind,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Eq, PartialEq)]
+pub struct Command {
+    // Identifies the command in diagnostics, must be unique within a tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     pub value: String,
-    #[serde(default, alias = "shell", deserialize_with = "deserialize_kind")]
+    #[serde(
+        default,
+        alias = "shell",
+        rename(serialize = "shell"),
+        deserialize_with = "deserialize_kind",
+        serialize_with = "serialize_kind",
+        skip_serializing_if = "CommandKind::is_initial"
+    )]
     pub kind: CommandKind,
-    #[serde(default)]
+    // Built-in generator of the level opened by a `next` action, replaces the shell command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    // Working directory of the shell command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+    // Heading the item is grouped under in the list of its parent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    // Second line of the row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    // Right-aligned text of the row, e.g. a shortcut, file size or branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessory: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<Badge>,
+    // Local PNG, JPEG or SVG shown as thumbnail in the row and as preview when selected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Layout::is_list")]
+    pub layout: Layout,
+    // Columns of the `grid` layout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<usize>,
     #[serde(default)]
     pub action: ActionKind,
-    #[serde(default, deserialize_with = "Items::deserialize")]
+    // Show the last result of a `next` action instantly and refresh it in the background
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CachePolicy>,
+    // Seconds after which the level opened by a `next` action is generated again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "Items::deserialize",
+        skip_serializing_if = "Items::is_empty"
+    )]
     pub items: Items<Command>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alt_actions: Vec<AltAction>,
 }
 
 #[cfg(test)]
@@ -165,8 +298,9 @@ impl<'de> Deserialize<'de> for CommandKind {
     }
 }
 
-// Deserialize kind from a simple value:
-// {"shell": "ls"} -> CommandKind::SyncShellCommand { command: "ls" }
+// Deserialize kind from a simple value or a kind object:
+// "ls" -> CommandKind::Shell { command: "ls" }
+// {"shell": "ls"} -> CommandKind::Shell { command: "ls" }
 fn deserialize_kind<'de, D>(deserializer: D) -> Result<CommandKind, D::Error>
 where
     D: Deserializer<'de>,
@@ -176,10 +310,38 @@ where
         Value::String(shell_string) => Ok(CommandKind::Shell(ShellProperties {
             command: shell_string.to_owned(),
         })),
+        Value::Object(_) => CommandKind::deserialize(obj).map_err(serde::de::Error::custom),
         _ => Ok(CommandKind::Initial),
     }
 }
 
+impl<'de> Deserialize<'de> for Badge {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct BadgeObject {
+            text: String,
+            #[serde(default)]
+            color: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BadgeValue {
+            Text(String),
+            Object(BadgeObject),
+        }
+
+        match BadgeValue::deserialize(deserializer)? {
+            BadgeValue::Text(text) => Ok(Badge { text, color: None }),
+            BadgeValue::Object(BadgeObject { text, color }) => Ok(Badge { text, color }),
+        }
+    }
+}
+
 // Deserialize items from a flat array to Items<Command>
 impl<'de> Deserialize<'de> for Items<Command> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -216,6 +378,54 @@ impl<'de> Deserialize<'de> for Items<Command> {
     }
 }
 
+// Serialization ---------------------------------------------------------------
+
+// Serialize kind to the object read by its `Deserialize` impl:
+// CommandKind::Shell { command: "ls" } -> {"shell": "ls"}
+impl Serialize for CommandKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        if let CommandKind::Shell(shell) = self {
+            map.serialize_entry("shell", &shell.command)?;
+        }
+        map.end()
+    }
+}
+
+// Serialize kind to the simple value that `deserialize_kind` reads:
+// CommandKind::Shell { command: "ls" } -> "ls"
+fn serialize_kind<S>(kind: &CommandKind, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match kind {
+        CommandKind::Shell(shell) => serializer.serialize_str(&shell.command),
+        CommandKind::Initial => serializer.serialize_none(),
+    }
+}
+
+// Serialize items to a flat array in their display order
+impl Serialize for Items<Command> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let commands: Vec<&Command> = self
+            .order
+            .iter()
+            .filter_map(|id| self.items.get(id))
+            .collect();
+        let mut seq = serializer.serialize_seq(Some(commands.len()))?;
+        for command in commands {
+            seq.serialize_element(command)?;
+        }
+        seq.end()
+    }
+}
+
 #[cfg(test)]
 mod deserialize_tests {
     use super::{Command, CommandKind, ShellProperties};
@@ -265,20 +475,124 @@ mod deserialize_tests {
         assert_eq!(v.value, "Commands");
         assert_eq!(v.items.order.len(), 0);
     }
+
+    #[test]
+    fn serializes_to_user_friendly_json() {
+        let data = include_str!("../../data/user_friendly_simple.json");
+        let v: Command = serde_json::from_str(data).unwrap();
+
+        let json = serde_json::to_value(&v).unwrap();
+
+        assert_eq!(json["items"][0]["value"], "~");
+        assert_eq!(json["items"][1]["shell"], "ls");
+        assert_eq!(json["items"][1]["action"], "print");
+        assert!(json.get("shell").is_none());
+
+        let round_trip: Command = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&round_trip).unwrap(), json);
+    }
+
+    #[test]
+    fn deserializes_kind_objects() {
+        let data = r#"{ "value": "ls", "kind": { "shell": "ls -la" } }"#;
+
+        let v: Command = serde_json::from_str(data).unwrap();
+        let kind = CommandKind::Shell(ShellProperties {
+            command: s!("ls -la"),
+        });
+
+        assert_eq!(v.kind, kind);
+        assert_eq!(
+            serde_json::to_value(&kind).unwrap(),
+            serde_json::json!({ "shell": "ls -la" })
+        );
+        assert_eq!(serde_json::to_value(&v).unwrap()["shell"], "ls -la");
+    }
 }
 
 // Impl ------------------------------------------------------------------------
 
+impl Default for RowMetrics {
+    fn default() -> Self {
+        RowMetrics {
+            row_height: SIMPLE_CMD_HEIGHT,
+            spacing: 1.,
+            // Default button padding of 5 on the top and bottom
+            padding: 10.,
+            header_height: 24.,
+            subtitle_height: 17.,
+            cell_height: 72.,
+        }
+    }
+}
+
+impl Layout {
+    pub fn is_list(&self) -> bool {
+        matches!(self, Layout::List)
+    }
+}
+
+impl fmt::Display for CommandResultError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandResultError::FailedWithCode(stderr, code) => {
+                write!(f, "Failed with code {}: {}", code, stderr.trim_end())
+            }
+            CommandResultError::ExecutionFailed(message) => write!(f, "{}", message),
+            CommandResultError::JsonParseError(err) => write!(f, "Invalid JSON output: {}", err),
+        }
+    }
+}
+
+impl<T> Items<T> {
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+impl<T> FromIterator<T> for Items<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items = Items {
+            items: HashMap::new(),
+            order: vec![],
+        };
+        for item in iter {
+            let id = Uuid::new_v4();
+            items.order.push(id);
+            items.items.insert(id, item);
+        }
+        items
+    }
+}
+
 impl CommandKind {
-    pub fn sync_execute(
+    pub fn is_initial(&self) -> bool {
+        matches!(self, CommandKind::Initial)
+    }
+
+    /// Run the shell command without blocking the executor, so jobs can run concurrently.
+    /// The `context` is written as JSON to its stdin.
+    pub async fn execute_shell(
         shell_command: ShellProperties,
         cmd: Command,
+        context: Option<ExecutionContext>,
     ) -> Result<String, CommandResultError> {
-        let output = process::Command::new("sh")
+        let stdin = match context {
+            Some(context) => Stdio::from(context.to_stdin().map_err(|err| {
+                CommandResultError::ExecutionFailed(format!("Failed to write the context: {}", err))
+            })?),
+            None => Stdio::null(),
+        };
+        let mut process = process::Command::new("sh");
+        process
             .arg("-c")
             .env("__COMMAND_VALUE", cmd.value)
             .arg(shell_command.command)
-            .output();
+            .stdin(stdin);
+        if let Some(cwd) = cmd.cwd {
+            process.current_dir(cwd);
+        }
+        let output = process.output().await;
 
         match output {
             Ok(output) => {
@@ -323,18 +637,78 @@ impl Command {
     }
 
     pub fn filter_items_by_value(&self, substring: &str) -> Vec<Uuid> {
-        let mut items: Vec<(usize, Uuid)> = self.map_filter_items(|_, id, command| {
+        // Rank of each section by its first item, to keep the matches grouped by their
+        // section in the order of the sections
+        let mut section_ranks: HashMap<Option<&str>, usize> = HashMap::new();
+        for (index, id) in self.items.order.iter().enumerate() {
+            if let Some(cmd) = self.items.items.get(id) {
+                section_ranks.entry(cmd.section.as_deref()).or_insert(index);
+            }
+        }
+
+        let lowercase_substring = substring.to_lowercase();
+        let mut items: Vec<(usize, usize, Uuid)> = self.map_filter_items(|_, id, command| {
             let value = &command.value;
-            let matches_value = value.to_lowercase().contains(&substring.to_lowercase());
+            let matches_value = value.to_lowercase().contains(&lowercase_substring);
             if matches_value {
-                Some((levenshtein(value, substring), *id))
+                let section_rank = section_ranks[&command.section.as_deref()];
+                Some((section_rank, levenshtein(value, substring), *id))
             } else {
                 None
             }
         });
-        items.sort_by(|(a, _), (b, _)| a.cmp(b));
+        items.sort_by_key(|(section_rank, distance, _)| (*section_rank, *distance));
 
-        items.into_iter().map(|(_, v)| v).collect()
+        items.into_iter().map(|(_, _, id)| id).collect()
+    }
+
+    /// Heading to show above the item at `index`, when it starts a new section.
+    pub fn section_header(&self, index: usize) -> Option<&str> {
+        let section_at = |index: usize| {
+            self.items
+                .order
+                .get(index)
+                .and_then(|id| self.items.items.get(id))
+                .and_then(|cmd| cmd.section.as_deref())
+        };
+        let section = section_at(index)?;
+        match index {
+            0 => Some(section),
+            _ if section_at(index - 1) != Some(section) => Some(section),
+            _ => None,
+        }
+    }
+
+    /// Id of the item that stands for `item`, e.g. after reloading or refreshing the items.
+    /// Items are matched by their `id` when they have one, otherwise by their value.
+    pub fn id_of_matching_item(&self, item: &Command) -> Option<Uuid> {
+        match &item.id {
+            Some(stable_id) => self
+                .items
+                .order
+                .iter()
+                .find(|id| {
+                    self.items
+                        .items
+                        .get(id)
+                        .is_some_and(|cmd| cmd.id.as_ref() == Some(stable_id))
+                })
+                .copied(),
+            None => self.id_of_item_with_value(&item.value),
+        }
+    }
+
+    pub fn id_of_item_with_value(&self, value: &str) -> Option<Uuid> {
+        self.items
+            .order
+            .iter()
+            .find(|id| {
+                self.items
+                    .items
+                    .get(id)
+                    .is_some_and(|cmd| cmd.value == value)
+            })
+            .copied()
     }
 
     pub fn index_of_item_with_id(&self, id: Uuid) -> Option<usize> {
@@ -362,28 +736,158 @@ impl Command {
         }
     }
 
-    pub fn command_kind_height(&self) -> f32 {
-        SIMPLE_CMD_HEIGHT
-            // Spacing
-            + 1.
-            // Figure out what this value is
-            + 10.
+    /// Height of the row content, rows with a subtitle span two lines.
+    pub fn row_content_height(&self, metrics: &RowMetrics) -> f32 {
+        match self.subtitle {
+            Some(_) => metrics.row_height + metrics.subtitle_height,
+            None => metrics.row_height,
+        }
+    }
+
+    pub fn command_kind_height(&self, metrics: &RowMetrics) -> f32 {
+        self.row_content_height(metrics) + metrics.spacing + metrics.padding
+    }
+
+    /// Items per row, 1 for lists.
+    pub fn columns(&self) -> usize {
+        match self.layout {
+            Layout::List => 1,
+            Layout::Grid => self.columns.unwrap_or(DEFAULT_GRID_COLUMNS).max(1),
+        }
     }
 
-    pub fn scroll_offset_at_index(&self, index: usize) -> f32 {
+    pub fn row_count(&self) -> usize {
+        self.items.order.len().div_ceil(self.columns())
+    }
+
+    pub fn grid_row_height(&self, metrics: &RowMetrics) -> f32 {
+        metrics.cell_height + metrics.spacing + metrics.padding
+    }
+
+    /// Offset of the item at `index` or of its section header.
+    /// In a grid this is the offset of the row of the item.
+    pub fn scroll_offset_at_index(&self, index: usize, metrics: &RowMetrics) -> f32 {
+        if self.layout == Layout::Grid {
+            return (index / self.columns()) as f32 * self.grid_row_height(metrics);
+        }
+
         let ids = &self.items.order[..index];
         let mut offset = 0.;
-        for id in ids {
+        for (idx, id) in ids.iter().enumerate() {
             offset += &self
                 .items
                 .items
                 .get(id)
-                .map(Command::command_kind_height)
-                .unwrap_or(0.)
+                .map(|cmd| cmd.command_kind_height(metrics))
+                .unwrap_or(0.);
+            if self.section_header(idx).is_some() {
+                offset += metrics.header_height + metrics.spacing;
+            }
         }
         offset
     }
 
+    /// Build the command that runs `alt_action` with the value of this command.
+    pub fn with_alt_action(&self, alt_action: &AltAction) -> Command {
+        // Without a shell command of its own the action runs the command with another action
+        let (kind, provider) = match &alt_action.kind {
+            CommandKind::Initial => (self.kind.clone(), self.provider.clone()),
+            kind => (kind.clone(), None),
+        };
+        let title = alt_action
+            .title
+            .clone()
+            .or_else(|| alt_action.key.map(|key| key.to_string()));
+
+        Command {
+            title,
+            value: self.value.clone(),
+            kind,
+            provider,
+            cwd: self.cwd.clone(),
+            icon: self.icon.clone(),
+            action: alt_action.action.clone(),
+            ..Command::default()
+        }
+    }
+
+    /// The command with the templates in `title`, `value`, `shell`, `icon` and `cwd` expanded,
+    /// `parent` is the level the command is an item of.
+    /// The shell command only gets the quoted parent fields, the shell expands the rest itself.
+    pub fn expanded(&self, parent: Option<&Command>) -> Command {
+        let expand = |field: &str| template::expand(field, parent);
+        let expand_kind = |kind: &CommandKind| match kind {
+            CommandKind::Initial => CommandKind::Initial,
+            CommandKind::Shell(shell_command) => CommandKind::Shell(ShellProperties {
+                command: template::expand_shell(&shell_command.command, parent),
+            }),
+        };
+
+        Command {
+            title: self.title.as_deref().map(expand),
+            value: expand(&self.value),
+            kind: expand_kind(&self.kind),
+            cwd: self.cwd.as_deref().map(expand),
+            icon: self.icon.as_deref().map(expand),
+            alt_actions: self
+                .alt_actions
+                .iter()
+                .map(|alt_action| AltAction {
+                    kind: expand_kind(&alt_action.kind),
+                    ..alt_action.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The level with the templates of its items expanded, as they are displayed and run.
+    pub fn with_expanded_items(&self) -> Command {
+        let items = self
+            .items
+            .items
+            .iter()
+            .map(|(id, item)| (*id, item.expanded(Some(self))))
+            .collect();
+
+        Command {
+            items: Items {
+                items,
+                order: self.items.order.clone(),
+            },
+            ..self.clone()
+        }
+    }
+
+    pub fn alt_action_for_key(&self, key: &KeyChord) -> Option<Command> {
+        self.alt_actions
+            .iter()
+            .find(|alt_action| alt_action.key.as_ref() == Some(key))
+            .map(|alt_action| self.with_alt_action(alt_action))
+    }
+
+    /// Level listing the primary action followed by all alternative actions.
+    pub fn action_menu(&self) -> Option<Command> {
+        if self.alt_actions.is_empty() {
+            return None;
+        }
+
+        let title = self.title.as_deref().unwrap_or(&self.value);
+        let items = std::iter::once(self.clone())
+            .chain(
+                self.alt_actions
+                    .iter()
+                    .map(|alt_action| self.with_alt_action(alt_action)),
+            )
+            .collect();
+
+        Some(Command {
+            value: format!("Actions: {}", title),
+            items,
+            ..Command::default()
+        })
+    }
+
     pub fn command_string(&self) -> String {
         match &self.kind {
             CommandKind::Initial => self.value.clone(),
@@ -391,15 +895,44 @@ impl Command {
         }
     }
 
-    pub fn execute(self) -> Result<String, CommandResultError> {
+    pub async fn execute(
+        self,
+        context: Option<ExecutionContext>,
+    ) -> Result<String, CommandResultError> {
         match &self.kind {
             CommandKind::Initial => Ok(self.value.clone()),
             CommandKind::Shell(shell_command) => {
-                CommandKind::sync_execute(shell_command.clone(), self)
+                CommandKind::execute_shell(shell_command.clone(), self, context).await
             }
         }
     }
 
+    /// The level opened by a `next` action, built by the provider or parsed from the output.
+    pub async fn generate(
+        self,
+        context: Option<ExecutionContext>,
+    ) -> Result<Command, CommandResultError> {
+        match self.provider {
+            // Providers read the system synchronously, so they must not block the executor
+            Some(provider) => {
+                async_std::task::spawn_blocking(move || providers::generate(&provider)).await
+            }
+            None => self
+                .execute(context)
+                .await
+                .and_then(|output| Command::parse(&output)),
+        }
+    }
+
+    /// Key of the cached result of the command, when it has a cache policy.
+    pub fn cache_key(&self) -> Option<String> {
+        let policy = self.cache.as_ref()?;
+        Some(match policy.by_value {
+            true => format!("{}\n{}", self.command_string(), self.value),
+            false => self.command_string(),
+        })
+    }
+
     pub fn parse(input: &str) -> Result<Command, CommandResultError> {
         serde_json::from_str(input).map_err(CommandResultError::JsonParseError)
     }
@@ -410,9 +943,18 @@ mod command_tests {
     use crate::s;
 
     use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use async_std::task::block_on;
+    use iced::futures::future;
     use uuid::Uuid;
 
-    use super::{ActionKind, Command, CommandKind, CommandResultError, Items, ShellProperties};
+    use super::{
+        ActionKind, AltAction, Badge, Command, CommandKind, CommandResultError, Items, Layout,
+        RowMetrics, ShellProperties,
+    };
+    use crate::core::context::ExecutionContext;
+    use crate::utils::temp_dir::TempDir;
 
     fn make_test_command() -> Command {
         let command_a_uuid = Uuid::new_v4();
@@ -486,13 +1028,92 @@ mod command_tests {
             ..Command::default()
         };
 
-        let result = command.execute();
+        let result = block_on(command.execute(None));
         assert!(result.is_ok());
 
         let value = result.unwrap();
         assert_eq!(value, "Success: Ok");
     }
 
+    #[test]
+    fn executes_commands_in_cwd() {
+        let temp_dir = TempDir::new("cwd");
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let command = Command {
+            value: s!("Ok"),
+            kind: CommandKind::Shell(ShellProperties { command: s!("pwd") }),
+            cwd: Some(dir.display().to_string()),
+            ..Command::default()
+        };
+
+        let result = block_on(command.execute(None)).unwrap();
+        assert_eq!(result, dir.display().to_string());
+    }
+
+    #[test]
+    fn writes_the_context_to_stdin() {
+        let command = Command {
+            value: s!("src"),
+            kind: CommandKind::Shell(ShellProperties { command: s!("cat") }),
+            ..Command::default()
+        };
+        let context = ExecutionContext {
+            item: command.clone(),
+            query: s!("sr"),
+            ..ExecutionContext::default()
+        };
+
+        let output = block_on(command.execute(Some(context.clone()))).unwrap();
+        let read: ExecutionContext = serde_json::from_str(&output).unwrap();
+        assert_eq!(read, context);
+    }
+
+    #[test]
+    fn expands_templates_of_items_with_their_level() {
+        let data = r#"{
+    "value": "api",
+    "items": [
+        {
+            "value": "Logs of {parent.value}",
+            "shell": "docker logs {parent.value} $__COMMAND_VALUE",
+            "cwd": "~/projects/{parent.value}",
+            "alt_actions": [{ "key": "ctrl+enter", "shell": "docker restart {parent.value}" }]
+        }
+    ]
+}"#;
+        let level = Command::parse(data).unwrap().with_expanded_items();
+        let (_, item) = level.get_child_command_by_index(0).unwrap();
+        let home = dirs::home_dir().unwrap();
+
+        assert_eq!(item.value, "Logs of api");
+        assert_eq!(item.command_string(), "docker logs 'api' $__COMMAND_VALUE");
+        assert_eq!(item.cwd, Some(format!("{}/projects/api", home.display())));
+        assert!(matches!(
+            &item.alt_actions[0].kind,
+            CommandKind::Shell(shell) if shell.command == "docker restart 'api'"
+        ));
+    }
+
+    #[test]
+    fn executes_commands_concurrently() {
+        let sleep = |value: &str| Command {
+            value: value.to_string(),
+            kind: CommandKind::Shell(ShellProperties {
+                command: s!("sleep 0.5; echo $__COMMAND_VALUE"),
+            }),
+            ..Command::default()
+        };
+
+        let started = Instant::now();
+        let (a, b) = block_on(future::join(
+            sleep("a").execute(None),
+            sleep("b").execute(None),
+        ));
+
+        assert_eq!((a.unwrap(), b.unwrap()), (s!("a"), s!("b")));
+        assert!(started.elapsed() < Duration::from_millis(900));
+    }
+
     #[test]
     fn execute_failing_command() {
         let command = Command {
@@ -503,22 +1124,203 @@ mod command_tests {
             ..Command::default()
         };
 
-        let result = command.execute();
+        let result = block_on(command.execute(None));
         assert!(result.is_err());
 
         let error = result.unwrap_err();
         let expected_error = CommandResultError::FailedWithCode(String::from("Fail\n"), 1);
 
         let is_expected_error = match &error {
-            CommandResultError::FailedWithCode(msg, code) => {
-                msg.clone() == String::from("Fail\n") && code.clone() == 1
-            }
+            CommandResultError::FailedWithCode(msg, code) => msg == "Fail\n" && *code == 1,
             _ => false,
         };
-        assert_eq!(
-            is_expected_error, true,
+        assert!(
+            is_expected_error,
             "Testing equality of errors {:#?} and {:#?}",
             error, expected_error
         )
     }
+
+    #[test]
+    fn deserializes_alt_actions() {
+        let data = r#"{
+    "value": "~/notes.txt",
+    "shell": "xdg-open $__COMMAND_VALUE",
+    "alt_actions": [
+        { "key": "ctrl+enter", "title": "Reveal", "shell": "nautilus $__COMMAND_VALUE" },
+        { "title": "Copy path", "shell": "echo $__COMMAND_VALUE", "action": "print" }
+    ]
+}"#;
+
+        let command = Command::parse(data).unwrap();
+        assert_eq!(command.alt_actions.len(), 2);
+
+        let key = "ctrl+enter".parse().unwrap();
+        let reveal = command.alt_action_for_key(&key).unwrap();
+        assert_eq!(reveal.value, "~/notes.txt");
+        assert_eq!(reveal.title, Some(s!("Reveal")));
+        assert_eq!(
+            reveal.kind,
+            CommandKind::Shell(ShellProperties {
+                command: s!("nautilus $__COMMAND_VALUE")
+            })
+        );
+
+        assert!(command
+            .alt_action_for_key(&"ctrl+k".parse().unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_alt_action_keys() {
+        let data = r#"{ "value": "x", "alt_actions": [{ "key": "hyper+enter" }] }"#;
+
+        assert!(Command::parse(data).is_err());
+    }
+
+    #[test]
+    fn builds_action_menu() {
+        let command = Command {
+            value: s!("~/notes.txt"),
+            alt_actions: vec![AltAction {
+                title: Some(s!("Copy path")),
+                action: ActionKind::Print,
+                ..AltAction::default()
+            }],
+            ..make_test_command()
+        };
+
+        let menu = command.action_menu().unwrap();
+        let values = menu.map_filter_items(|_, _, cmd| Some(cmd.value.clone()));
+        assert_eq!(values, vec![s!("~/notes.txt"), s!("~/notes.txt")]);
+
+        let (_, copy_path) = menu.get_child_command_by_index(1).unwrap();
+        assert_eq!(copy_path.action, ActionKind::Print);
+        // Alt actions without a shell reuse the kind of their command
+        assert_eq!(copy_path.kind, command.kind);
+
+        assert!(make_test_command().action_menu().is_none());
+    }
+
+    #[test]
+    fn scroll_offset_uses_row_metrics() {
+        let command = make_test_command();
+        let metrics = RowMetrics {
+            row_height: 40.,
+            ..RowMetrics::default()
+        };
+
+        assert_eq!(command.scroll_offset_at_index(0, &metrics), 0.);
+        assert_eq!(command.scroll_offset_at_index(1, &metrics), 51.);
+        assert_eq!(
+            command.scroll_offset_at_index(2, &RowMetrics::default()),
+            78.
+        );
+    }
+
+    fn sectioned_command() -> Command {
+        let data = r#"{
+    "value": "Commands",
+    "items": [
+        { "value": "notes.md", "section": "Recent" },
+        { "value": "firefox", "section": "Applications" },
+        { "value": "files", "section": "Applications" },
+        { "value": "notes", "section": "Files" }
+    ]
+}"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn deserializes_rich_rows() {
+        let data = r#"{
+    "value": "Commands",
+    "items": [
+        { "value": "iced-prompt", "subtitle": "~/code", "accessory": "main", "badges": ["wip", { "text": "2", "color": "error" }] },
+        { "value": "notes" }
+    ]
+}"#;
+
+        let command: Command = serde_json::from_str(data).unwrap();
+        let (_, rich) = command.get_child_command_by_index(0).unwrap();
+
+        assert_eq!(rich.subtitle.as_deref(), Some("~/code"));
+        assert_eq!(rich.accessory.as_deref(), Some("main"));
+        assert_eq!(
+            rich.badges,
+            vec![
+                Badge {
+                    text: s!("wip"),
+                    color: None
+                },
+                Badge {
+                    text: s!("2"),
+                    color: Some(s!("error"))
+                },
+            ]
+        );
+        // The two-line row adds the subtitle height to the scroll offsets
+        let metrics = RowMetrics::default();
+        assert_eq!(rich.command_kind_height(&metrics), 56.);
+        assert_eq!(command.scroll_offset_at_index(1, &metrics), 56.);
+        assert_eq!(command.scroll_offset_at_index(2, &metrics), 95.);
+    }
+
+    #[test]
+    fn scroll_offset_of_grids_is_per_row() {
+        let data = r#"{
+    "value": "Emoji",
+    "layout": "grid",
+    "columns": 3,
+    "items": [
+        { "value": "a" }, { "value": "b" }, { "value": "c" },
+        { "value": "d" }, { "value": "e" }
+    ]
+}"#;
+
+        let command: Command = serde_json::from_str(data).unwrap();
+        let metrics = RowMetrics::default();
+
+        assert_eq!(command.layout, Layout::Grid);
+        assert_eq!(command.columns(), 3);
+        assert_eq!(command.row_count(), 2);
+        assert_eq!(command.scroll_offset_at_index(2, &metrics), 0.);
+        assert_eq!(command.scroll_offset_at_index(4, &metrics), 83.);
+        assert_eq!(make_test_command().columns(), 1);
+    }
+
+    #[test]
+    fn shows_headers_at_section_starts() {
+        let command = sectioned_command();
+
+        let headers: Vec<Option<&str>> = (0..4).map(|i| command.section_header(i)).collect();
+
+        assert_eq!(
+            headers,
+            vec![Some("Recent"), Some("Applications"), None, Some("Files")]
+        );
+        // Two rows of 39 and the headers of both of their sections
+        assert_eq!(
+            command.scroll_offset_at_index(2, &RowMetrics::default()),
+            128.
+        );
+    }
+
+    #[test]
+    fn filtering_keeps_matches_grouped_by_section() {
+        let command = sectioned_command();
+
+        let filtered = command.with_order(command.filter_items_by_value("notes"));
+
+        let values: Vec<&str> = filtered
+            .items
+            .order
+            .iter()
+            .map(|id| filtered.items.items[id].value.as_str())
+            .collect();
+        // The closer match of a later section does not move before earlier sections
+        assert_eq!(values, vec!["notes.md", "notes"]);
+        assert_eq!(filtered.section_header(0), Some("Recent"));
+        assert_eq!(filtered.section_header(1), Some("Files"));
+    }
 }
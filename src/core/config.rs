@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::core::commands::{RowMetrics, SIMPLE_CMD_HEIGHT};
+use crate::core::geometry::WindowConfig;
+use crate::core::keymap::{Keymap, KeymapError, Preset};
+use crate::core::theme::{ColorPalette, Theme};
+use crate::s;
+
+// Constants -------------------------------------------------------------------
+
+pub const DEFAULT_CONFIG_PATH: &str = "~/.config/iced_prompt/config.json";
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    preset: Preset,
+    // Maps a key chord like "ctrl+j" to an action name like "select-next"
+    #[serde(default)]
+    bindings: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    // Family name of a system font or of the font loaded from `path`
+    pub family: Option<String>,
+    // Font file to load, requires the `family` it contains
+    pub path: Option<String>,
+    // Text size of the rows
+    pub size: f32,
+    pub input_size: f32,
+    pub footer_size: f32,
+    pub row_height: f32,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    keymap: KeymapFile,
+    #[serde(default)]
+    window: WindowConfig,
+    // Name of a built-in theme or path to a palette file
+    #[serde(default)]
+    theme: Option<String>,
+    #[serde(default)]
+    font: FontConfig,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub keymap: Keymap,
+    pub window: WindowConfig,
+    pub palette: ColorPalette,
+    pub font: FontConfig,
+    // Content of the font file at `font.path`
+    pub font_data: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Keymap(PathBuf, KeymapError),
+    UnknownTheme(String),
+    Palette(PathBuf, String),
+    Font(PathBuf, String),
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        FontConfig {
+            family: None,
+            path: None,
+            size: 16.,
+            input_size: 15.,
+            footer_size: 13.,
+            row_height: SIMPLE_CMD_HEIGHT,
+        }
+    }
+}
+
+impl FontConfig {
+    pub fn row_metrics(&self) -> RowMetrics {
+        RowMetrics {
+            row_height: self.row_height,
+            // Text uses a line height of 1.3
+            subtitle_height: (self.footer_size * 1.3).ceil(),
+            ..RowMetrics::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let sizes = [
+            ("size", self.size),
+            ("input_size", self.input_size),
+            ("footer_size", self.footer_size),
+            ("row_height", self.row_height),
+        ];
+        if let Some((name, _)) = sizes.iter().find(|(_, size)| *size <= 0.) {
+            return Err(format!("font.{} must be greater than 0", name));
+        }
+        if self.path.is_some() && self.family.is_none() {
+            return Err(s!("font.path requires the font.family it contains"));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::Keymap(path, err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::UnknownTheme(name) => write!(
+                f,
+                "Unknown theme `{}`, expected one of: lupin, dark, light or a path to a .toml or .json palette",
+                name
+            ),
+            ConfigError::Palette(path, err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::Font(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config at `path`, or the default config location when no path is given.
+    /// A missing file at the default location is not an error and yields the default config.
+    pub fn load(path: Option<&str>) -> Result<Config, ConfigError> {
+        let is_explicit = path.is_some();
+        let path = PathBuf::from(shellexpand::tilde(path.unwrap_or(DEFAULT_CONFIG_PATH)).as_ref());
+
+        match fs::read_to_string(&path) {
+            Ok(json_string) => Config::parse(&path, &json_string),
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !is_explicit => {
+                Config::parse(&path, "{}")
+            }
+            Err(err) => Err(ConfigError::Io(path, err)),
+        }
+    }
+
+    pub fn parse(path: &Path, json_str: &str) -> Result<Config, ConfigError> {
+        let file: ConfigFile = serde_json::from_str(json_str)
+            .map_err(|err| ConfigError::Parse(path.to_path_buf(), err))?;
+
+        let bindings = file
+            .keymap
+            .bindings
+            .iter()
+            .map(|(chord, action)| (chord.as_str(), action.as_str()));
+        let keymap = Keymap::new(file.keymap.preset, bindings)
+            .map_err(|err| ConfigError::Keymap(path.to_path_buf(), err))?;
+
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let palette = match &file.theme {
+            Some(theme) => load_palette(theme, base_dir)?,
+            None => ColorPalette::default(),
+        };
+
+        file.font
+            .validate()
+            .map_err(|err| ConfigError::Font(path.to_path_buf(), err))?;
+        let font_data = match &file.font.path {
+            Some(font_path) => {
+                let font_path = base_dir.join(shellexpand::tilde(font_path).as_ref());
+                let data = fs::read(&font_path).map_err(|err| ConfigError::Io(font_path, err))?;
+                Some(data)
+            }
+            None => None,
+        };
+
+        Ok(Config {
+            keymap,
+            window: file.window,
+            palette,
+            font: file.font,
+            font_data,
+        })
+    }
+}
+
+/// Resolve `theme` to a built-in palette or load a palette file.
+/// Relative paths are resolved against `base_dir`.
+pub fn load_palette(theme: &str, base_dir: &Path) -> Result<ColorPalette, ConfigError> {
+    if let Ok(builtin) = theme.parse::<Theme>() {
+        return Ok(builtin.palette());
+    }
+
+    let path = base_dir.join(shellexpand::tilde(theme).as_ref());
+    let extension = path.extension().and_then(|e| e.to_str());
+    if !matches!(extension, Some("toml" | "json")) {
+        return Err(ConfigError::UnknownTheme(theme.to_string()));
+    }
+
+    let content = fs::read_to_string(&path).map_err(|err| ConfigError::Io(path.clone(), err))?;
+    let palette = match extension {
+        Some("toml") => toml::from_str(&content).map_err(|err| err.to_string()),
+        _ => serde_json::from_str(&content).map_err(|err| err.to_string()),
+    };
+    palette.map_err(|err| ConfigError::Palette(path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use iced::keyboard::{KeyCode, Modifiers};
+
+    use super::{load_palette, Config, ConfigError};
+    use crate::core::geometry::Anchor;
+    use crate::core::keymap::Action;
+    use crate::core::theme::Theme;
+
+    #[test]
+    fn parses_keymap_section() {
+        let data = r#"{
+    "keymap": {
+        "preset": "emacs",
+        "bindings": { "ctrl+q": "quit" }
+    }
+}"#;
+
+        let config = Config::parse(Path::new("config.json"), data).unwrap();
+
+        assert_eq!(
+            config.keymap.action(KeyCode::G, Modifiers::CTRL),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            config.keymap.action(KeyCode::Q, Modifiers::CTRL),
+            Some(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn parses_window_section() {
+        let data = r#"{ "window": { "width": 1200, "position": "cursor" } }"#;
+
+        let config = Config::parse(Path::new("config.json"), data).unwrap();
+
+        assert_eq!(config.window.width, 1200);
+        assert_eq!(config.window.height, 500);
+        assert_eq!(config.window.position, Anchor::Cursor);
+    }
+
+    #[test]
+    fn resolves_themes() {
+        let data = r#"{ "theme": "light" }"#;
+
+        let config = Config::parse(Path::new("config.json"), data).unwrap();
+        assert_eq!(config.palette, Theme::Light.palette());
+
+        assert!(matches!(
+            load_palette("solarized", Path::new(".")),
+            Err(ConfigError::UnknownTheme(_))
+        ));
+        assert!(matches!(
+            load_palette("missing.toml", Path::new(".")),
+            Err(ConfigError::Io(_, _))
+        ));
+    }
+
+    #[test]
+    fn parses_font_section() {
+        let data = r#"{ "font": { "family": "Inter", "size": 18, "row_height": 36 } }"#;
+
+        let config = Config::parse(Path::new("config.json"), data).unwrap();
+
+        assert_eq!(config.font.family.as_deref(), Some("Inter"));
+        assert_eq!(config.font.size, 18.);
+        assert_eq!(config.font.input_size, 15.);
+        assert_eq!(config.font.row_metrics().row_height, 36.);
+        assert_eq!(config.font_data, None);
+    }
+
+    #[test]
+    fn rejects_invalid_fonts() {
+        let without_family = r#"{ "font": { "path": "Inter.ttf" } }"#;
+        let zero_height = r#"{ "font": { "row_height": 0 } }"#;
+        let missing_file = r#"{ "font": { "family": "Inter", "path": "missing.ttf" } }"#;
+
+        assert!(matches!(
+            Config::parse(Path::new("config.json"), without_family),
+            Err(ConfigError::Font(_, _))
+        ));
+        assert!(matches!(
+            Config::parse(Path::new("config.json"), zero_height),
+            Err(ConfigError::Font(_, _))
+        ));
+        assert!(matches!(
+            Config::parse(Path::new("config.json"), missing_file),
+            Err(ConfigError::Io(_, _))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_presets() {
+        let unknown_action = r#"{ "keymap": { "bindings": { "ctrl+q": "exit" } } }"#;
+        let unknown_preset = r#"{ "keymap": { "preset": "nano" } }"#;
+
+        assert!(matches!(
+            Config::parse(Path::new("config.json"), unknown_action),
+            Err(ConfigError::Keymap(_, _))
+        ));
+        assert!(matches!(
+            Config::parse(Path::new("config.json"), unknown_preset),
+            Err(ConfigError::Parse(_, _))
+        ));
+    }
+}
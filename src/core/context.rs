@@ -0,0 +1,105 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::process;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::core::commands::Command;
+use crate::core::history::History;
+
+// Types -----------------------------------------------------------------------
+
+/// Where a command was run from, written as JSON to the stdin of its shell command.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ExecutionContext {
+    // The item the command was run for, with all of its fields
+    pub item: Command,
+    // Text typed into the prompt
+    pub query: String,
+    // Values of the levels from the root to the level of the item
+    pub path: Vec<String>,
+    // Items the command runs on, only `item` as items can't be marked yet
+    pub selection: Vec<Command>,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl ExecutionContext {
+    /// Context of running `item` of the current level of `history` while `query` is typed.
+    pub fn new(item: Command, query: &str, history: &History) -> Self {
+        ExecutionContext {
+            selection: vec![item.clone()],
+            item,
+            query: query.to_string(),
+            path: history.levels().map(|level| level.value.clone()).collect(),
+        }
+    }
+
+    /// The context as JSON in an already deleted temporary file, to be used as stdin.
+    /// Unlike a pipe it can be left unread without blocking the prompt.
+    pub fn to_stdin(&self) -> io::Result<File> {
+        let json_string = serde_json::to_string(self)?;
+        let path = std::env::temp_dir().join(format!(
+            "iced_prompt-context-{}-{}.json",
+            process::id(),
+            Uuid::new_v4()
+        ));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // The open file stays readable after its path is removed
+        let result = file
+            .write_all(json_string.as_bytes())
+            .and_then(|_| file.seek(SeekFrom::Start(0)));
+        fs::remove_file(&path)?;
+        result.map(|_| file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::ExecutionContext;
+    use crate::core::commands::Command;
+    use crate::core::history::History;
+    use crate::s;
+
+    fn command(value: &str) -> Command {
+        Command {
+            value: s!(value),
+            ..Command::default()
+        }
+    }
+
+    #[test]
+    fn describes_the_path_to_the_item() {
+        let history = History::default()
+            .push(command("Projects"))
+            .push(command("iced-prompt"));
+
+        let context = ExecutionContext::new(command("src"), "sr", &history);
+
+        assert_eq!(context.path, vec!["Projects", "iced-prompt"]);
+        assert_eq!(context.query, "sr");
+        assert_eq!(context.selection, vec![command("src")]);
+    }
+
+    #[test]
+    fn writes_json_to_stdin() {
+        let context = ExecutionContext::new(command("src"), "", &History::default());
+
+        let mut json_string = String::new();
+        context
+            .to_stdin()
+            .unwrap()
+            .read_to_string(&mut json_string)
+            .unwrap();
+
+        let read: ExecutionContext = serde_json::from_str(&json_string).unwrap();
+        assert_eq!(read, context);
+    }
+}
@@ -1,5 +1,6 @@
 use nix::sys::signal::SigSet;
 use nix::unistd::{fork, setsid, ForkResult};
+use std::fs::File;
 use std::io;
 use std::os::unix::process::CommandExt;
 use std::process::{self, Stdio};
@@ -7,10 +8,14 @@ use std::process::{self, Stdio};
 /// Spawn unsupervised daemons.
 ///
 /// This function double-forks to avoid spawning zombies and launches a program with arguments.
-pub fn exec(program: String) -> io::Result<()> {
+/// The program reads `stdin` and runs in the directory `cwd` when given.
+pub fn exec(program: String, stdin: Option<File>, cwd: Option<&str>) -> io::Result<()> {
     let mut command = process::Command::new("sh");
     command.args(["-c", &program]);
-    command.stdin(Stdio::null());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    command.stdin(stdin.map_or(Stdio::null(), Stdio::from));
     command.stdout(Stdio::null());
     command.stderr(Stdio::null());
 
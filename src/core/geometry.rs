@@ -0,0 +1,171 @@
+use std::process;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Anchor {
+    // Centered on the monitor by the windowing system
+    #[default]
+    Center,
+    // At the top left corner of the screen
+    Top,
+    // At the mouse cursor
+    Cursor,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    // Size in logical pixels
+    pub width: u32,
+    pub height: u32,
+    pub position: Anchor,
+    // Offset in pixels relative to the `top` and `cursor` anchors
+    pub offset_x: i32,
+    pub offset_y: i32,
+    // Shrink the window to fit at most this many rows
+    pub max_rows: Option<usize>,
+}
+
+/// Window size and position resolved from the `WindowConfig`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Geometry {
+    pub width: u32,
+    pub height: u32,
+    // `None` centers the window on the monitor
+    pub position: Option<(i32, i32)>,
+    pub max_rows: Option<usize>,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: 700,
+            height: 500,
+            position: Anchor::Center,
+            offset_x: 0,
+            offset_y: 0,
+            max_rows: None,
+        }
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "center" => Ok(Anchor::Center),
+            "top" => Ok(Anchor::Top),
+            "cursor" => Ok(Anchor::Cursor),
+            _ => Err(format!(
+                "Invalid position `{}`, expected one of: center, top, cursor",
+                s
+            )),
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn geometry(&self, cursor: Option<(i32, i32)>) -> Geometry {
+        let offset = |(x, y): (i32, i32)| Some((x + self.offset_x, y + self.offset_y));
+        let position = match (self.position, cursor) {
+            (Anchor::Center, _) | (Anchor::Cursor, None) => None,
+            (Anchor::Top, _) => offset((0, 0)),
+            (Anchor::Cursor, Some(cursor)) => offset(cursor),
+        };
+
+        Geometry {
+            width: self.width,
+            height: self.height,
+            position,
+            max_rows: self.max_rows,
+        }
+    }
+
+    /// Resolve the geometry against the current cursor position.
+    pub fn detect_geometry(&self) -> Geometry {
+        let cursor = match self.position {
+            Anchor::Cursor => cursor_position(),
+            _ => None,
+        };
+        self.geometry(cursor)
+    }
+}
+
+/// Current mouse cursor position in X11 screen coordinates as reported by `xdotool`.
+/// `None` on Wayland without XWayland or without `xdotool`, the window is centered then.
+pub fn cursor_position() -> Option<(i32, i32)> {
+    let output = process::Command::new("xdotool")
+        .args(["getmouselocation", "--shell"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let coordinate = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .and_then(|value| value.parse().ok())
+    };
+    Some((coordinate("X")?, coordinate("Y")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Anchor, Geometry, WindowConfig};
+
+    #[test]
+    fn deserializes_window_config() {
+        let config: WindowConfig = serde_json::from_str(
+            r#"{ "width": 1920, "height": 40, "position": "top", "max_rows": 8 }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.width, 1920);
+        assert_eq!(config.height, 40);
+        assert_eq!(config.position, Anchor::Top);
+        assert_eq!(config.max_rows, Some(8));
+        assert!(serde_json::from_str::<WindowConfig>(r#"{ "width": "50%" }"#).is_err());
+    }
+
+    #[test]
+    fn centers_by_default() {
+        let geometry = WindowConfig::default().geometry(None);
+
+        assert_eq!(
+            geometry,
+            Geometry {
+                width: 700,
+                height: 500,
+                position: None,
+                max_rows: None,
+            }
+        );
+    }
+
+    #[test]
+    fn anchors_to_top_and_cursor() {
+        let top = WindowConfig {
+            position: Anchor::Top,
+            offset_y: 10,
+            ..WindowConfig::default()
+        };
+        assert_eq!(top.geometry(None).position, Some((0, 10)));
+
+        let cursor = WindowConfig {
+            position: Anchor::Cursor,
+            offset_x: 20,
+            ..WindowConfig::default()
+        };
+        assert_eq!(cursor.geometry(Some((100, 100))).position, Some((120, 100)));
+        // Falls back to the center without a cursor position
+        assert_eq!(cursor.geometry(None).position, None);
+    }
+}
@@ -1,63 +1,256 @@
+use uuid::Uuid;
+
 use crate::core::commands::Command;
 use crate::utils::list::SinglyLinkedList;
 
+/// UI state of a level, restored when navigating back to it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ViewState {
+    pub query: String,
+    pub selected: Option<Uuid>,
+    pub scroll_offset: f32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HistoryEntry {
+    // Identifies the level while it is on the stack, also when its command is replaced
+    pub id: Uuid,
+    pub command: Command,
+    pub view: ViewState,
+    // The `next` command whose output is the level, to generate it again
+    pub generator: Option<Command>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct History {
-    pub history: SinglyLinkedList<Command>,
+    pub history: SinglyLinkedList<HistoryEntry>,
+    // Levels left by navigating backwards, the next one to restore is last
+    pub forward: Vec<HistoryEntry>,
 }
 
-#[allow(dead_code)]
 impl History {
     pub fn push(self, cmds: Command) -> History {
         let mut cmds_list = self.history.clone();
-        cmds_list.push(cmds);
-        History { history: cmds_list }
+        cmds_list.push(HistoryEntry {
+            id: Uuid::new_v4(),
+            command: cmds,
+            view: ViewState::default(),
+            generator: None,
+        });
+        History {
+            history: cmds_list,
+            forward: vec![],
+        }
+    }
+
+    /// Push the output of the `generator` command.
+    pub fn push_generated(self, cmds: Command, generator: Command) -> History {
+        let mut history = self.push(cmds);
+        if let Some(entry) = history.history.pop() {
+            history.history.push(HistoryEntry {
+                generator: Some(generator),
+                ..entry
+            });
+        }
+        history
     }
 
     pub fn pop(self) -> History {
         let mut cmds_list = self.history.clone();
         cmds_list.pop();
-        History { history: cmds_list }
+        History {
+            history: cmds_list,
+            forward: self.forward,
+        }
     }
 
+    /// Navigate backwards, keeping at least the root level.
+    /// The level that is left can be restored with `go_forward`.
     pub fn pop_with_minimum(self) -> History {
         if self.history.len() != 1 {
             let mut cmds_list = self.history.clone();
-            cmds_list.pop();
-            History { history: cmds_list }
+            let mut forward = self.forward;
+            forward.extend(cmds_list.pop());
+            History {
+                history: cmds_list,
+                forward,
+            }
         } else {
             self
         }
     }
 
+    /// Restore the level that was last left by navigating backwards.
+    pub fn go_forward(self) -> History {
+        let mut forward = self.forward;
+        let mut cmds_list = self.history;
+        if let Some(entry) = forward.pop() {
+            cmds_list.push(entry);
+        }
+        History {
+            history: cmds_list,
+            forward,
+        }
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
     pub fn head(&self) -> Option<Command> {
-        let mut cmds_list = self.history.clone();
-        cmds_list.pop()
+        self.history
+            .iter()
+            .next()
+            .map(|entry| entry.command.clone())
+    }
+
+    pub fn head_id(&self) -> Option<Uuid> {
+        self.history.iter().next().map(|entry| entry.id)
+    }
+
+    pub fn head_view(&self) -> Option<ViewState> {
+        self.history.iter().next().map(|entry| entry.view.clone())
     }
 
-    pub fn split(self) -> Option<(Command, SinglyLinkedList<Command>)> {
+    pub fn head_generator(&self) -> Option<&Command> {
+        self.history.iter().next()?.generator.as_ref()
+    }
+
+    /// Store the UI state of the current level.
+    pub fn with_view(self, view: ViewState) -> History {
+        let mut cmds_list = self.history;
+        if let Some(entry) = cmds_list.pop() {
+            cmds_list.push(HistoryEntry { view, ..entry });
+        }
+        History {
+            history: cmds_list,
+            forward: self.forward,
+        }
+    }
+
+    /// Replace the root level with `command`, e.g. after the commands file changed.
+    /// The selection of the root level moves to the item with the same value.
+    pub fn replace_root(self, command: Command) -> History {
+        let mut entries: Vec<HistoryEntry> = self.history.iter().cloned().collect();
+        let Some(root) = entries.pop() else {
+            return self.push(command);
+        };
+
+        let selected = moved_selection(&root, &command);
+        let mut cmds_list = SinglyLinkedList::default();
+        cmds_list.push(HistoryEntry {
+            id: root.id,
+            command,
+            view: ViewState {
+                selected,
+                ..root.view
+            },
+            generator: None,
+        });
+        for entry in entries.into_iter().rev() {
+            cmds_list.push(entry);
+        }
+        History {
+            history: cmds_list,
+            forward: self.forward,
+        }
+    }
+
+    /// Replace the current level with `command`, e.g. with a refreshed result.
+    /// The selection moves to the item with the same value.
+    pub fn replace_head(self, command: Command) -> History {
+        let mut cmds_list = self.history;
+        let Some(head) = cmds_list.pop() else {
+            return History {
+                history: cmds_list,
+                forward: self.forward,
+            }
+            .push(command);
+        };
+
+        let selected = moved_selection(&head, &command);
+        cmds_list.push(HistoryEntry {
+            id: head.id,
+            command,
+            view: ViewState {
+                selected,
+                ..head.view
+            },
+            generator: head.generator,
+        });
+        History {
+            history: cmds_list,
+            forward: self.forward,
+        }
+    }
+
+    pub fn split(self) -> Option<(Command, SinglyLinkedList<HistoryEntry>)> {
         let mut cmds_list = self.history.clone();
-        cmds_list.pop().map(|cmds| (cmds, cmds_list.clone()))
+        cmds_list
+            .pop()
+            .map(|entry| (entry.command, cmds_list.clone()))
     }
 
     pub fn len(self) -> usize {
         self.history.len()
     }
+
+    pub fn is_empty(self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Iterate over the levels, starting at the root.
+    pub fn levels(&self) -> impl Iterator<Item = &Command> {
+        let levels: Vec<&Command> = self.history.iter().map(|entry| &entry.command).collect();
+        levels.into_iter().rev()
+    }
+
+    /// Keep the first `depth` levels from the root, at least the root itself.
+    /// The levels that are left can be restored with `go_forward`.
+    pub fn truncate(self, depth: usize) -> History {
+        let mut cmds_list = self.history.clone();
+        let mut forward = self.forward;
+        while cmds_list.len() > depth.max(1) {
+            forward.extend(cmds_list.pop());
+        }
+        History {
+            history: cmds_list,
+            forward,
+        }
+    }
+}
+
+// Id of the item of `command` that matches the item selected in `entry`
+fn moved_selection(entry: &HistoryEntry, command: &Command) -> Option<Uuid> {
+    entry
+        .view
+        .selected
+        .and_then(|id| entry.command.items.items.get(&id))
+        .and_then(|item| command.id_of_matching_item(item))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Command, History};
+    use uuid::Uuid;
+
+    use super::{Command, History, ViewState};
+    use crate::core::commands::Items;
 
+    #[test]
     fn it_works() {
-        let history_with_two_items = History::default()
+        let history_with_one_item = History::default()
             .push(Command::default())
             .push(Command::default())
             .pop();
 
-        assert_eq!(history_with_two_items.len(), 2);
+        assert_eq!(history_with_one_item.len(), 1);
     }
 
+    #[test]
     fn test_head() {
         assert_eq!(History::default().head(), None);
         assert_eq!(
@@ -66,6 +259,7 @@ mod tests {
         );
     }
 
+    #[test]
     fn test_split() {
         let (head, tail) = History::default()
             .push(Command::default())
@@ -75,4 +269,133 @@ mod tests {
         assert_eq!(head, Command::default());
         assert_eq!(tail.len(), 1);
     }
+
+    fn command(value: &str) -> Command {
+        Command {
+            value: value.to_string(),
+            ..Command::default()
+        }
+    }
+
+    fn menu(values: &[&str]) -> Command {
+        let items: Vec<(Uuid, Command)> = values
+            .iter()
+            .map(|value| (Uuid::new_v4(), command(value)))
+            .collect();
+        Command {
+            items: Items {
+                order: items.iter().map(|(id, _)| *id).collect(),
+                items: items.into_iter().collect(),
+            },
+            ..command("root")
+        }
+    }
+
+    #[test]
+    fn iterates_levels_from_root() {
+        let history = History::default()
+            .push(command("root"))
+            .push(command("a"))
+            .push(command("b"));
+
+        let values: Vec<&str> = history.levels().map(|cmd| cmd.value.as_str()).collect();
+
+        assert_eq!(values, vec!["root", "a", "b"]);
+        assert_eq!(history.depth(), 3);
+    }
+
+    #[test]
+    fn truncates_to_depth() {
+        let history = History::default()
+            .push(command("root"))
+            .push(command("a"))
+            .push(command("b"));
+
+        assert_eq!(history.clone().truncate(2).head(), Some(command("a")));
+        assert_eq!(history.clone().truncate(0).head(), Some(command("root")));
+        assert_eq!(history.truncate(5).depth(), 3);
+    }
+
+    #[test]
+    fn restores_view_state_when_navigating_back() {
+        let view = ViewState {
+            query: "doc".to_string(),
+            selected: None,
+            scroll_offset: 39.,
+        };
+        let history = History::default()
+            .push(command("root"))
+            .with_view(view.clone())
+            .push(command("a"));
+
+        assert_eq!(history.head_view(), Some(ViewState::default()));
+        assert_eq!(history.pop_with_minimum().head_view(), Some(view));
+    }
+
+    #[test]
+    fn navigates_forward_to_left_levels() {
+        let history = History::default()
+            .push(command("root"))
+            .push(command("a"))
+            .push(command("b"))
+            .truncate(1);
+
+        assert!(history.can_go_forward());
+        let history = history.go_forward();
+        assert_eq!(history.head(), Some(command("a")));
+        let history = history.go_forward();
+        assert_eq!(history.head(), Some(command("b")));
+        assert!(!history.can_go_forward());
+
+        // Pushing a new level discards the levels that could be restored
+        let history = history.pop_with_minimum().push(command("c"));
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn replaces_root_and_keeps_selection_by_value() {
+        let root = menu(&["a", "b"]);
+        let selected = root.items.order[1];
+        let history = History::default()
+            .push(root)
+            .with_view(ViewState {
+                query: "b".to_string(),
+                selected: Some(selected),
+                scroll_offset: 0.,
+            })
+            .push(command("child"));
+
+        let reloaded = menu(&["new", "b"]);
+        let history = history.replace_root(reloaded.clone());
+
+        assert_eq!(history.head(), Some(command("child")));
+        let history = history.pop_with_minimum();
+        let view = history.head_view().unwrap();
+        assert_eq!(history.head(), Some(reloaded.clone()));
+        assert_eq!(view.query, "b");
+        assert_eq!(view.selected, Some(reloaded.items.order[1]));
+    }
+
+    #[test]
+    fn replaces_head_and_keeps_selection_by_value() {
+        let level = menu(&["a", "b"]);
+        let selected = level.items.order[0];
+        let history = History::default()
+            .push(command("root"))
+            .push(level)
+            .with_view(ViewState {
+                selected: Some(selected),
+                ..ViewState::default()
+            });
+
+        let refreshed = menu(&["c", "a"]);
+        let history = history.replace_head(refreshed.clone());
+
+        assert_eq!(history.depth(), 2);
+        assert_eq!(history.head(), Some(refreshed.clone()));
+        assert_eq!(
+            history.head_view().unwrap().selected,
+            Some(refreshed.items.order[1])
+        );
+    }
 }
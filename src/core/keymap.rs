@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use iced::keyboard::{KeyCode, Modifiers};
+use serde::de::Deserializer;
+use serde::{Deserialize, Serialize, Serializer};
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Action {
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    // Move the selection within a row of a grid
+    SelectLeft,
+    SelectRight,
+    PageDown,
+    PageUp,
+    HistoryBack,
+    // Return to the level left by navigating back
+    HistoryForward,
+    // Jump to the level at the given depth, the root being 1
+    HistoryJump(usize),
+    // Submit the selected item with its own `ActionKind`
+    Submit,
+    // Submit the selected item but force the `ActionKind`
+    SubmitExit,
+    SubmitPrint,
+    // List the alternative actions of the selected item
+    ActionMenu,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Emacs,
+    Vim,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum KeymapError {
+    UnknownAction { chord: String, action: String },
+    InvalidChord { chord: String, reason: String },
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Keymap {
+    pub bindings: HashMap<KeyChord, Action>,
+}
+
+// Constants -------------------------------------------------------------------
+
+const ACTIONS: &[(&str, Action)] = &[
+    ("select-next", Action::SelectNext),
+    ("select-previous", Action::SelectPrevious),
+    ("select-first", Action::SelectFirst),
+    ("select-last", Action::SelectLast),
+    ("select-left", Action::SelectLeft),
+    ("select-right", Action::SelectRight),
+    ("page-down", Action::PageDown),
+    ("page-up", Action::PageUp),
+    ("history-back", Action::HistoryBack),
+    ("history-forward", Action::HistoryForward),
+    ("submit", Action::Submit),
+    ("submit-exit", Action::SubmitExit),
+    ("submit-print", Action::SubmitPrint),
+    ("action-menu", Action::ActionMenu),
+    ("quit", Action::Quit),
+];
+
+// Action name that removes a binding inherited from the preset
+const UNBIND: &str = "none";
+
+// Prefix of the actions jumping to a level: "history-jump-2"
+const HISTORY_JUMP: &str = "history-jump-";
+
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("enter", "submit"),
+    ("shift+enter", "submit-print"),
+    ("shift+backspace", "history-back"),
+    ("shift+tab", "history-back"),
+    ("alt+left", "history-back"),
+    ("alt+right", "history-forward"),
+    ("escape", "quit"),
+    ("up", "select-previous"),
+    ("down", "select-next"),
+    ("left", "select-left"),
+    ("right", "select-right"),
+    ("ctrl+p", "select-previous"),
+    ("ctrl+n", "select-next"),
+    ("pageup", "page-up"),
+    ("pagedown", "page-down"),
+    ("ctrl+home", "select-first"),
+    ("ctrl+end", "select-last"),
+    ("ctrl+k", "action-menu"),
+    ("alt+1", "history-jump-1"),
+    ("alt+2", "history-jump-2"),
+    ("alt+3", "history-jump-3"),
+    ("alt+4", "history-jump-4"),
+    ("alt+5", "history-jump-5"),
+    ("alt+6", "history-jump-6"),
+    ("alt+7", "history-jump-7"),
+    ("alt+8", "history-jump-8"),
+    ("alt+9", "history-jump-9"),
+];
+
+const EMACS_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl+g", "quit"),
+    ("ctrl+j", "submit"),
+    ("ctrl+m", "submit"),
+    ("ctrl+v", "page-down"),
+    ("alt+v", "page-up"),
+    ("alt+shift+comma", "select-first"),
+    ("alt+shift+period", "select-last"),
+];
+
+const VIM_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl+j", "select-next"),
+    ("ctrl+k", "select-previous"),
+    ("ctrl+d", "page-down"),
+    ("ctrl+u", "page-up"),
+    ("ctrl+y", "submit"),
+    ("ctrl+o", "history-back"),
+    ("ctrl+i", "history-forward"),
+    ("ctrl+a", "action-menu"),
+    ("ctrl+lbracket", "quit"),
+];
+
+// Names of the keys in chords, the canonical name of a key comes before its aliases
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("a", KeyCode::A),
+    ("b", KeyCode::B),
+    ("c", KeyCode::C),
+    ("d", KeyCode::D),
+    ("e", KeyCode::E),
+    ("f", KeyCode::F),
+    ("g", KeyCode::G),
+    ("h", KeyCode::H),
+    ("i", KeyCode::I),
+    ("j", KeyCode::J),
+    ("k", KeyCode::K),
+    ("l", KeyCode::L),
+    ("m", KeyCode::M),
+    ("n", KeyCode::N),
+    ("o", KeyCode::O),
+    ("p", KeyCode::P),
+    ("q", KeyCode::Q),
+    ("r", KeyCode::R),
+    ("s", KeyCode::S),
+    ("t", KeyCode::T),
+    ("u", KeyCode::U),
+    ("v", KeyCode::V),
+    ("w", KeyCode::W),
+    ("x", KeyCode::X),
+    ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("f1", KeyCode::F1),
+    ("f2", KeyCode::F2),
+    ("f3", KeyCode::F3),
+    ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6),
+    ("f7", KeyCode::F7),
+    ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9),
+    ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11),
+    ("f12", KeyCode::F12),
+    ("enter", KeyCode::Enter),
+    ("return", KeyCode::Enter),
+    ("escape", KeyCode::Escape),
+    ("esc", KeyCode::Escape),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("space", KeyCode::Space),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("comma", KeyCode::Comma),
+    (",", KeyCode::Comma),
+    ("period", KeyCode::Period),
+    (".", KeyCode::Period),
+    ("slash", KeyCode::Slash),
+    ("/", KeyCode::Slash),
+    ("semicolon", KeyCode::Semicolon),
+    (";", KeyCode::Semicolon),
+    ("minus", KeyCode::Minus),
+    ("-", KeyCode::Minus),
+    ("equals", KeyCode::Equals),
+    ("=", KeyCode::Equals),
+    ("lbracket", KeyCode::LBracket),
+    ("[", KeyCode::LBracket),
+    ("rbracket", KeyCode::RBracket),
+    ("]", KeyCode::RBracket),
+];
+
+// Impl ------------------------------------------------------------------------
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(depth) = s.strip_prefix(HISTORY_JUMP) {
+            return match depth.parse::<usize>() {
+                Ok(depth) if depth > 0 => Ok(Action::HistoryJump(depth)),
+                _ => Err(()),
+            };
+        }
+
+        ACTIONS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, action)| *action)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Action::HistoryJump(depth) = self {
+            return write!(f, "{}{}", HISTORY_JUMP, depth);
+        }
+
+        let name = ACTIONS
+            .iter()
+            .find(|(_, action)| action == self)
+            .map_or("unknown", |(name, _)| name);
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| KeymapError::InvalidChord {
+            chord: s.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let lowercase = s.to_lowercase();
+        let mut parts: Vec<&str> = lowercase.split('+').map(str::trim).collect();
+        let key_name = parts
+            .pop()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| invalid("missing key"))?;
+
+        let mut modifiers = Modifiers::empty();
+        for part in parts {
+            let modifier = match part {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "meta" => Modifiers::ALT,
+                "super" | "logo" | "cmd" => Modifiers::LOGO,
+                other => return Err(invalid(&format!("unknown modifier `{}`", other))),
+            };
+            modifiers |= modifier;
+        }
+
+        let key = key_code_from_name(key_name)
+            .ok_or_else(|| invalid(&format!("unknown key `{}`", key_name)))?;
+
+        Ok(KeyChord { key, modifiers })
+    }
+}
+
+// Deserialize a key chord from its string form: "ctrl+enter"
+impl<'de> Deserialize<'de> for KeyChord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let chord = String::deserialize(deserializer)?;
+        chord.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// Serialize a key chord to its string form, the inverse of `Deserialize`
+impl Serialize for KeyChord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let modifiers = [
+            (Modifiers::CTRL, "ctrl"),
+            (Modifiers::ALT, "alt"),
+            (Modifiers::SHIFT, "shift"),
+            (Modifiers::LOGO, "super"),
+        ];
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match key_name(self.key) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", format!("{:?}", self.key).to_lowercase()),
+        }
+    }
+}
+
+/// Canonical name of `key`, the first of its names in `KEY_NAMES`.
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, code)| *code == key)
+        .map(|(name, _)| *name)
+}
+
+fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| *key_name == name)
+        .map(|(_, code)| *code)
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeymapError::UnknownAction { chord, action } => {
+                let mut valid: Vec<&str> = ACTIONS.iter().map(|(name, _)| *name).collect();
+                valid.push("history-jump-<depth>");
+                valid.push(UNBIND);
+                write!(
+                    f,
+                    "Unknown action `{}` bound to `{}`, expected one of: {}",
+                    action,
+                    chord,
+                    valid.join(", ")
+                )
+            }
+            KeymapError::InvalidChord { chord, reason } => {
+                write!(f, "Invalid key chord `{}`: {}", chord, reason)
+            }
+        }
+    }
+}
+
+impl Preset {
+    fn bindings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Preset::Default => &[],
+            Preset::Emacs => EMACS_BINDINGS,
+            Preset::Vim => VIM_BINDINGS,
+        }
+    }
+}
+
+impl Keymap {
+    /// Build a keymap from a preset and user bindings (`chord -> action`).
+    /// User bindings take precedence over the preset, which itself extends the default bindings.
+    pub fn new<'a, I>(preset: Preset, bindings: I) -> Result<Keymap, KeymapError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut keymap = Keymap::default();
+
+        let preset_bindings = DEFAULT_BINDINGS.iter().chain(preset.bindings()).copied();
+        for (chord, action) in preset_bindings.chain(bindings) {
+            let key_chord: KeyChord = chord.parse()?;
+            if action == UNBIND {
+                keymap.bindings.remove(&key_chord);
+                continue;
+            }
+
+            let action: Action = action.parse().map_err(|_| KeymapError::UnknownAction {
+                chord: chord.to_string(),
+                action: action.to_string(),
+            })?;
+            keymap.bindings.insert(key_chord, action);
+        }
+
+        Ok(keymap)
+    }
+
+    /// The action bound to the chord, unbound chords fall back to the binding of the key alone,
+    /// so e.g. escape quits and up selects with any modifier held.
+    pub fn action(&self, key: KeyCode, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .get(&KeyChord { key, modifiers })
+            .or_else(|| {
+                self.bindings.get(&KeyChord {
+                    key,
+                    modifiers: Modifiers::empty(),
+                })
+            })
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::keyboard::{KeyCode, Modifiers};
+
+    use super::{Action, KeyChord, Keymap, KeymapError, Preset, KEY_NAMES};
+
+    #[test]
+    fn parses_key_chords() {
+        let chord: KeyChord = "ctrl+shift+k".parse().unwrap();
+        assert_eq!(chord.key, KeyCode::K);
+        assert_eq!(chord.modifiers, Modifiers::CTRL | Modifiers::SHIFT);
+
+        let chord: KeyChord = "Enter".parse().unwrap();
+        assert_eq!(chord.key, KeyCode::Enter);
+        assert_eq!(chord.modifiers, Modifiers::empty());
+
+        let chord: KeyChord = "alt+f5".parse().unwrap();
+        assert_eq!(chord.key, KeyCode::F5);
+    }
+
+    #[test]
+    fn prints_chords_that_parse_again() {
+        for (name, _) in KEY_NAMES {
+            for chord in [name.to_string(), format!("ctrl+alt+{}", name)] {
+                let parsed: KeyChord = chord.parse().unwrap();
+                assert_eq!(parsed.to_string().parse(), Ok(parsed), "{}", chord);
+            }
+        }
+        assert_eq!("Alt+1".parse::<KeyChord>().unwrap().to_string(), "alt+1");
+    }
+
+    #[test]
+    fn rejects_invalid_chords() {
+        assert!(matches!(
+            "hyper+k".parse::<KeyChord>(),
+            Err(KeymapError::InvalidChord { .. })
+        ));
+        assert!(matches!(
+            "ctrl+".parse::<KeyChord>(),
+            Err(KeymapError::InvalidChord { .. })
+        ));
+        assert!(matches!(
+            "f13".parse::<KeyChord>(),
+            Err(KeymapError::InvalidChord { .. })
+        ));
+    }
+
+    #[test]
+    fn default_preset_keeps_builtin_bindings() {
+        let keymap = Keymap::new(Preset::Default, []).unwrap();
+
+        assert_eq!(
+            keymap.action(KeyCode::Enter, Modifiers::empty()),
+            Some(Action::Submit)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::N, Modifiers::CTRL),
+            Some(Action::SelectNext)
+        );
+        assert_eq!(keymap.action(KeyCode::J, Modifiers::CTRL), None);
+    }
+
+    #[test]
+    fn falls_back_to_the_key_without_modifiers() {
+        let keymap = Keymap::new(Preset::Default, []).unwrap();
+
+        assert_eq!(
+            keymap.action(KeyCode::Escape, Modifiers::SHIFT),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Up, Modifiers::CTRL),
+            Some(Action::SelectPrevious)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Enter, Modifiers::SHIFT),
+            Some(Action::SubmitPrint)
+        );
+        assert_eq!(keymap.action(KeyCode::K, Modifiers::SHIFT), None);
+    }
+
+    #[test]
+    fn presets_and_user_bindings_override_defaults() {
+        let keymap = Keymap::new(
+            Preset::Vim,
+            [
+                ("ctrl+q", "quit"),
+                ("escape", "none"),
+                ("ctrl+n", "page-down"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            keymap.action(KeyCode::J, Modifiers::CTRL),
+            Some(Action::SelectNext)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Q, Modifiers::CTRL),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(KeyCode::Escape, Modifiers::empty()), None);
+        assert_eq!(
+            keymap.action(KeyCode::N, Modifiers::CTRL),
+            Some(Action::PageDown)
+        );
+    }
+
+    #[test]
+    fn parses_history_jumps() {
+        assert_eq!("history-jump-3".parse(), Ok(Action::HistoryJump(3)));
+        assert_eq!("history-jump-0".parse::<Action>(), Err(()));
+        assert_eq!("history-jump-".parse::<Action>(), Err(()));
+        assert_eq!(Action::HistoryJump(12).to_string(), "history-jump-12");
+
+        let keymap = Keymap::new(Preset::Default, []).unwrap();
+        assert_eq!(
+            keymap.action(KeyCode::Key1, Modifiers::ALT),
+            Some(Action::HistoryJump(1))
+        );
+    }
+
+    #[test]
+    fn reports_unknown_actions() {
+        let error = Keymap::new(Preset::Default, [("ctrl+x", "explode")]).unwrap_err();
+
+        assert_eq!(
+            error,
+            KeymapError::UnknownAction {
+                chord: "ctrl+x".to_string(),
+                action: "explode".to_string()
+            }
+        );
+        assert!(error.to_string().contains("select-next"));
+    }
+}
@@ -0,0 +1,376 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::core::commands::Command;
+
+// Constants -------------------------------------------------------------------
+
+// Key of a command whose files are spliced into its `items`
+const INCLUDE_KEY: &str = "include";
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug)]
+pub enum LoaderError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+    UnknownFormat(PathBuf),
+    Include(PathBuf, String),
+    // Files of the cycle, starting and ending with the same file
+    Cycle(Vec<PathBuf>),
+}
+
+/// Where a commands tree was read from, to reload it when they change.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Sources {
+    // Every file that was read, as canonical paths
+    pub files: Vec<PathBuf>,
+    // Include patterns with wildcards as absolute paths, files created later can match them
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct LoadState {
+    // Files that are currently being loaded, to detect cycles
+    stack: Vec<PathBuf>,
+    sources: Sources,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoaderError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            LoaderError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            LoaderError::UnknownFormat(path) => write!(
+                f,
+                "{}: Unknown format, expected a .json, .toml, .yaml or .yml file",
+                path.display()
+            ),
+            LoaderError::Include(path, err) => write!(f, "{}: {}", path.display(), err),
+            LoaderError::Cycle(paths) => {
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "Include cycle: {}", paths.join(" -> "))
+            }
+        }
+    }
+}
+
+/// Load the commands tree at `path` with all includes resolved, along with the files it
+/// was read from, including the file itself, and the include patterns.
+pub fn load(path: &Path) -> Result<(Value, Sources), LoaderError> {
+    let mut state = LoadState::default();
+    let value = load_file(path, &mut state)?;
+    Ok((value, state.sources))
+}
+
+/// Load the command at `path` along with its sources, see `load`.
+pub fn load_command_with_sources(path: &Path) -> Result<(Command, Sources), LoaderError> {
+    let (value, sources) = load(path)?;
+    let command = serde_json::from_value(value)
+        .map_err(|err| LoaderError::Parse(path.to_path_buf(), err.to_string()))?;
+    Ok((command, sources))
+}
+
+impl Sources {
+    /// Whether a change of the file at `path` can change the commands tree.
+    pub fn affects(&self, path: &Path) -> bool {
+        self.files.iter().any(|file| file == path)
+            || self.patterns.iter().any(|pattern| {
+                glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches_path(path))
+            })
+    }
+
+    /// Directories to watch for changes, recursively when the flag is set.
+    /// Editors often replace files instead of writing them, so their directories are watched.
+    pub fn directories(&self) -> Vec<(PathBuf, bool)> {
+        let mut directories: Vec<(PathBuf, bool)> = self
+            .files
+            .iter()
+            .filter_map(|file| Some((file.parent()?.to_path_buf(), false)))
+            .collect();
+        for pattern in &self.patterns {
+            // The directory before the first component with a wildcard
+            let directory: PathBuf = Path::new(pattern)
+                .components()
+                .take_while(|component| {
+                    !component
+                        .as_os_str()
+                        .to_string_lossy()
+                        .contains(['*', '?', '['])
+                })
+                .collect();
+            directories.push((directory, pattern.contains("**")));
+        }
+        directories.sort();
+        directories.dedup();
+        directories
+    }
+}
+
+fn load_file(path: &Path, state: &mut LoadState) -> Result<Value, LoaderError> {
+    let canonical =
+        fs::canonicalize(path).map_err(|err| LoaderError::Io(path.to_path_buf(), err))?;
+    if let Some(start) = state.stack.iter().position(|p| *p == canonical) {
+        let mut cycle = state.stack[start..].to_vec();
+        cycle.push(canonical);
+        return Err(LoaderError::Cycle(cycle));
+    }
+    if !state.sources.files.contains(&canonical) {
+        state.sources.files.push(canonical.clone());
+    }
+
+    let content =
+        fs::read_to_string(path).map_err(|err| LoaderError::Io(path.to_path_buf(), err))?;
+    let value = parse(path, &content)?;
+
+    // Includes are relative to the canonical directory, so the patterns match watched paths
+    let base_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+    state.stack.push(canonical);
+    let resolved = resolve_includes(value, path, &base_dir, state);
+    state.stack.pop();
+    resolved
+}
+
+/// Parse `content` in the format given by the extension of `path`, JSON by default.
+pub fn parse(path: &Path, content: &str) -> Result<Value, LoaderError> {
+    let parse_error = |err: String| LoaderError::Parse(path.to_path_buf(), err);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") | None => {
+            serde_json::from_str(content).map_err(|err| parse_error(err.to_string()))
+        }
+        Some("toml") => toml::from_str(content).map_err(|err| parse_error(err.to_string())),
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str(content).map_err(|err| parse_error(err.to_string()))
+        }
+        Some(_) => Err(LoaderError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+fn resolve_includes(
+    value: Value,
+    path: &Path,
+    base_dir: &Path,
+    state: &mut LoadState,
+) -> Result<Value, LoaderError> {
+    let include_error = |err: String| LoaderError::Include(path.to_path_buf(), err);
+
+    match value {
+        Value::Array(commands) => commands
+            .into_iter()
+            .map(|command| resolve_includes(command, path, base_dir, state))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(mut fields) => {
+            let mut items = match fields.remove("items") {
+                Some(Value::Array(items)) => items
+                    .into_iter()
+                    .map(|item| resolve_includes(item, path, base_dir, state))
+                    .collect::<Result<Vec<_>, _>>()?,
+                Some(other) => {
+                    return Err(include_error(format!(
+                        "Expected items to be an array, got {}",
+                        other
+                    )))
+                }
+                None => vec![],
+            };
+
+            let patterns = match fields.remove(INCLUDE_KEY) {
+                Some(Value::String(pattern)) => vec![pattern],
+                Some(Value::Array(patterns)) => patterns
+                    .into_iter()
+                    .map(|pattern| match pattern {
+                        Value::String(pattern) => Ok(pattern),
+                        other => Err(include_error(format!(
+                            "Expected an include path, got {}",
+                            other
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()?,
+                Some(other) => {
+                    return Err(include_error(format!(
+                        "Expected an include path, got {}",
+                        other
+                    )))
+                }
+                None => vec![],
+            };
+            for pattern in patterns {
+                if pattern.contains(['*', '?', '[']) {
+                    let full_pattern = base_dir.join(shellexpand::tilde(&pattern).as_ref());
+                    state
+                        .sources
+                        .patterns
+                        .push(full_pattern.to_string_lossy().into_owned());
+                }
+                for include_path in expand_pattern(&pattern, base_dir).map_err(include_error)? {
+                    match load_file(&include_path, state)? {
+                        Value::Array(commands) => items.extend(commands),
+                        command @ Value::Object(_) => items.push(command),
+                        other => {
+                            return Err(LoaderError::Include(
+                                include_path,
+                                format!("Expected a command or a list of commands, got {}", other),
+                            ))
+                        }
+                    }
+                }
+            }
+
+            if !items.is_empty() {
+                fields.insert("items".to_string(), Value::Array(items));
+            }
+            Ok(Value::Object(fields))
+        }
+        other => Ok(other),
+    }
+}
+
+// Resolve an include pattern relative to `base_dir` to the files it matches, in order.
+// A pattern without wildcards has to match its file.
+fn expand_pattern(pattern: &str, base_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let full_pattern = base_dir.join(shellexpand::tilde(pattern).as_ref());
+    let full_pattern = full_pattern.to_string_lossy();
+
+    let mut paths = glob::glob(&full_pattern)
+        .map_err(|err| format!("Invalid include pattern `{}`: {}", pattern, err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    paths.sort();
+
+    let is_literal = !pattern.contains(['*', '?', '[']);
+    if paths.is_empty() && is_literal {
+        return Err(format!("Included file `{}` does not exist", pattern));
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{load, load_command_with_sources, LoaderError};
+    use crate::utils::temp_dir::TempDir;
+
+    fn values(path: &Path) -> Vec<String> {
+        let (command, _) = load_command_with_sources(path).unwrap();
+        command
+            .items
+            .order
+            .iter()
+            .map(|id| command.items.items[id].value.clone())
+            .collect()
+    }
+
+    #[test]
+    fn loads_toml_and_yaml() {
+        let dir = TempDir::with_files(
+            "formats",
+            &[
+                (
+                    "commands.toml",
+                    "value = \"Commands\"\n\n[[items]]\nvalue = \"ls\"\nshell = \"ls\"\naction = \"print\"\n",
+                ),
+                ("commands.yaml", "value: Commands\nitems:\n  - value: ls\n    shell: ls\n"),
+            ],
+        );
+
+        assert_eq!(values(&dir.path().join("commands.toml")), vec!["ls"]);
+        assert_eq!(values(&dir.path().join("commands.yaml")), vec!["ls"]);
+        assert!(matches!(
+            load(&dir.path().join("missing.toml")),
+            Err(LoaderError::Io(_, _))
+        ));
+    }
+
+    #[test]
+    fn splices_included_files_in_order() {
+        let dir = TempDir::with_files(
+            "includes",
+            &[
+                (
+                    "commands.json",
+                    r#"{ "value": "Commands", "items": [{ "value": "first" }], "include": ["topics/*.yaml", "git.toml"] }"#,
+                ),
+                ("topics/a.yaml", "- value: a1\n- value: a2\n"),
+                ("topics/b.yaml", "value: b\n"),
+                ("git.toml", "value = \"git\"\ninclude = \"topics/b.yaml\"\n"),
+            ],
+        );
+
+        assert_eq!(
+            values(&dir.path().join("commands.json")),
+            vec!["first", "a1", "a2", "b", "git"]
+        );
+        let (command, sources) = load_command_with_sources(&dir.path().join("git.toml")).unwrap();
+        assert_eq!(command.items.order.len(), 1);
+        assert_eq!(sources.files.len(), 2);
+        assert!(sources.files[1].ends_with("topics/b.yaml"));
+        assert!(sources.patterns.is_empty());
+    }
+
+    #[test]
+    fn reports_sources_to_watch_for_new_files() {
+        let dir = TempDir::with_files(
+            "sources",
+            &[
+                (
+                    "commands.json",
+                    r#"{ "value": "Commands", "include": "topics/*.yaml" }"#,
+                ),
+                ("topics/a.yaml", "value: a\n"),
+            ],
+        );
+        let (_, sources) = load_command_with_sources(&dir.path().join("commands.json")).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+
+        assert!(sources.affects(&root.join("topics/a.yaml")));
+        assert!(sources.affects(&root.join("topics/new.yaml")));
+        assert!(!sources.affects(&root.join("topics/notes.txt")));
+        assert_eq!(
+            sources.directories(),
+            vec![(root.clone(), false), (root.join("topics"), false)]
+        );
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = TempDir::with_files(
+            "cycles",
+            &[
+                ("a.json", r#"{ "value": "a", "include": "b.yaml" }"#),
+                ("b.yaml", "value: b\ninclude: a.json\n"),
+            ],
+        );
+
+        match load(&dir.path().join("a.json")) {
+            Err(LoaderError::Cycle(paths)) => {
+                assert_eq!(paths.len(), 3);
+                assert_eq!(paths.first(), paths.last());
+            }
+            other => panic!("Expected a cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_includes() {
+        let dir = TempDir::with_files(
+            "missing",
+            &[(
+                "commands.json",
+                r#"{ "value": "a", "include": "nope.json" }"#,
+            )],
+        );
+
+        assert!(matches!(
+            load(&dir.path().join("commands.json")),
+            Err(LoaderError::Include(_, _))
+        ));
+    }
+}
@@ -1,4 +1,16 @@
+pub mod cache;
+pub mod check;
 pub mod commands;
+pub mod config;
+pub mod context;
 pub mod daemon;
+pub mod geometry;
 pub mod history;
+pub mod keymap;
+pub mod loader;
+pub mod providers;
+pub mod replay;
+pub mod schema;
+pub mod session;
+pub mod template;
 pub mod theme;
@@ -0,0 +1,41 @@
+pub mod processes;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::commands::{Command, CommandResultError};
+
+// Types -----------------------------------------------------------------------
+
+/// Built-in generator of a level, used instead of a shell command by `next` actions.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    // Running processes: "processes"
+    Processes,
+    // Signals that can be sent to a process: {"process": {"pid": 42}}
+    Process { pid: i32 },
+    // Send a signal and list the processes again: {"signal": {"pid": 42, "signal": "SIGTERM"}}
+    Signal { pid: i32, signal: String },
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl Provider {
+    /// Whether the level built by the provider replaces the level its menu was opened from,
+    /// instead of being pushed on top of the menu.
+    pub fn returns_to_parent(&self) -> bool {
+        matches!(self, Provider::Signal { .. })
+    }
+}
+
+/// Build the level of `provider`, this blocks while reading the system state.
+pub fn generate(provider: &Provider) -> Result<Command, CommandResultError> {
+    match provider {
+        Provider::Processes => processes::list(),
+        Provider::Process { pid } => processes::signal_menu(*pid),
+        Provider::Signal { pid, signal } => {
+            processes::send_signal(*pid, signal)?;
+            processes::list()
+        }
+    }
+}
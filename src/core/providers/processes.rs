@@ -0,0 +1,239 @@
+use std::fs;
+use std::str::FromStr;
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::{sysconf, Pid, SysconfVar};
+
+use crate::core::commands::{ActionKind, Command, CommandResultError};
+use crate::core::providers::Provider;
+
+// Constants -------------------------------------------------------------------
+
+// Signals offered for a process, the first one is selected
+const SIGNALS: &[(&str, &str)] = &[("SIGTERM", "Terminate"), ("SIGKILL", "Kill")];
+
+// Fallbacks when sysconf fails, the values of virtually every Linux system
+const DEFAULT_CLOCK_TICKS: u64 = 100;
+const DEFAULT_PAGE_SIZE: u64 = 4096;
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub name: String,
+    pub command_line: String,
+    // Average over the lifetime of the process
+    pub cpu_percent: f32,
+    // Resident set size
+    pub memory_bytes: u64,
+}
+
+// Values shared by all processes of one listing
+struct SystemInfo {
+    clock_ticks: u64,
+    page_size: u64,
+    uptime_seconds: f64,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl SystemInfo {
+    fn read() -> SystemInfo {
+        let sysconf_value = |var, default| match sysconf(var) {
+            Ok(Some(value)) if value > 0 => value as u64,
+            _ => default,
+        };
+        let uptime_seconds = fs::read_to_string("/proc/uptime")
+            .ok()
+            .and_then(|uptime| uptime.split_whitespace().next()?.parse().ok())
+            .unwrap_or(0.);
+
+        SystemInfo {
+            clock_ticks: sysconf_value(SysconfVar::CLK_TCK, DEFAULT_CLOCK_TICKS),
+            page_size: sysconf_value(SysconfVar::PAGE_SIZE, DEFAULT_PAGE_SIZE),
+            uptime_seconds,
+        }
+    }
+}
+
+/// Running processes, read from `/proc`.
+pub fn processes() -> Vec<ProcessInfo> {
+    let system = SystemInfo::read();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        // Processes can exit while they are read
+        .filter_map(|pid| read_process(pid, &system))
+        .collect()
+}
+
+fn read_process(pid: i32, system: &SystemInfo) -> Option<ProcessInfo> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The name is in parentheses and can contain spaces and parentheses itself
+    let name_start = stat.find('(')?;
+    let name_end = stat.rfind(')')?;
+    let name = stat[name_start + 1..name_end].to_string();
+    // Fields after the name, starting with the third field `state`
+    let fields: Vec<&str> = stat[name_end + 1..].split_whitespace().collect();
+    let field = |number: usize| -> Option<u64> { fields.get(number - 3)?.parse().ok() };
+
+    let cpu_seconds = (field(14)? + field(15)?) as f64 / system.clock_ticks as f64;
+    let started_seconds = field(22)? as f64 / system.clock_ticks as f64;
+    let running_seconds = system.uptime_seconds - started_seconds;
+    let cpu_percent = match running_seconds > 0. {
+        true => (cpu_seconds / running_seconds * 100.) as f32,
+        false => 0.,
+    };
+
+    let command_line = fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|bytes| {
+            let arguments: Vec<String> = bytes
+                .split(|byte| *byte == 0)
+                .filter(|argument| !argument.is_empty())
+                .map(|argument| String::from_utf8_lossy(argument).into_owned())
+                .collect();
+            arguments.join(" ")
+        })
+        .unwrap_or_default();
+
+    Some(ProcessInfo {
+        pid,
+        name,
+        command_line,
+        cpu_percent,
+        memory_bytes: field(24)? * system.page_size,
+    })
+}
+
+/// Level of the running processes, busiest first. Selecting one shows the signals for it.
+pub fn list() -> Result<Command, CommandResultError> {
+    let mut processes = processes();
+    processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+
+    let items = processes
+        .into_iter()
+        .map(|process| Command {
+            // Keeps the selection on the process when the list is refreshed
+            id: Some(process.pid.to_string()),
+            value: process.name,
+            subtitle: Some(process.command_line).filter(|command_line| !command_line.is_empty()),
+            accessory: Some(format!(
+                "{}  {:.1}%  {}",
+                process.pid,
+                process.cpu_percent,
+                format_memory(process.memory_bytes)
+            )),
+            provider: Some(Provider::Process { pid: process.pid }),
+            action: ActionKind::Next,
+            ..Command::default()
+        })
+        .collect();
+
+    Ok(Command {
+        value: "Processes".to_string(),
+        items,
+        ..Command::default()
+    })
+}
+
+/// Level to confirm which signal is sent to the process with `pid`.
+pub fn signal_menu(pid: i32) -> Result<Command, CommandResultError> {
+    let process = read_process(pid, &SystemInfo::read()).ok_or_else(|| {
+        CommandResultError::ExecutionFailed(format!("No process with pid {}", pid))
+    })?;
+
+    let items = SIGNALS
+        .iter()
+        .map(|(signal, title)| Command {
+            value: title.to_string(),
+            subtitle: Some(format!("Send {} to {} ({})", signal, process.name, pid)),
+            provider: Some(Provider::Signal {
+                pid,
+                signal: signal.to_string(),
+            }),
+            action: ActionKind::Next,
+            ..Command::default()
+        })
+        .collect();
+
+    Ok(Command {
+        value: format!("{} ({})", process.name, pid),
+        items,
+        ..Command::default()
+    })
+}
+
+/// Send the signal named `signal_name`, e.g. "SIGTERM", to the process with `pid`.
+pub fn send_signal(pid: i32, signal_name: &str) -> Result<(), CommandResultError> {
+    let signal = Signal::from_str(signal_name).map_err(|_| {
+        CommandResultError::ExecutionFailed(format!("Unknown signal {}", signal_name))
+    })?;
+    signal::kill(Pid::from_raw(pid), signal).map_err(|err| {
+        CommandResultError::ExecutionFailed(format!(
+            "Failed to send {} to {}: {}",
+            signal_name, pid, err
+        ))
+    })
+}
+
+fn format_memory(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit + 1 < UNITS.len() {
+        size /= 1024.;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, UNITS[0]),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process;
+
+    use super::{format_memory, list, processes, send_signal, signal_menu};
+    use crate::core::providers::{generate, Provider};
+
+    #[test]
+    fn lists_and_signals_processes() {
+        let mut child = process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+
+        let sleep = processes().into_iter().find(|p| p.pid == pid).unwrap();
+        assert_eq!(sleep.name, "sleep");
+        assert_eq!(sleep.command_line, "sleep 30");
+
+        let level = list().unwrap();
+        let id = level.id_of_item_with_value("sleep").unwrap();
+        assert!(level.items.items[&id].subtitle.is_some());
+
+        let menu = signal_menu(pid).unwrap();
+        let (_, terminate) = menu.get_child_command_by_index(0).unwrap();
+        let provider = terminate.provider.unwrap();
+        assert_eq!(
+            provider,
+            Provider::Signal {
+                pid,
+                signal: "SIGTERM".to_string()
+            }
+        );
+
+        generate(&provider).unwrap();
+        assert_eq!(child.wait().unwrap().signal(), Some(15));
+        assert!(send_signal(pid, "SIGNOPE").is_err());
+    }
+
+    #[test]
+    fn formats_memory() {
+        assert_eq!(format_memory(512), "512 B");
+        assert_eq!(format_memory(1536), "1.5 KiB");
+        assert_eq!(format_memory(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::core::keymap::{KeyChord, KeymapError};
+
+// Types -----------------------------------------------------------------------
+
+/// A simulated user input of a `--replay` script.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Step {
+    // Append text to the input
+    Type(String),
+    // Press a key chord, resolved against the keymap like a real key press
+    Key(KeyChord),
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    UnterminatedString(usize),
+    MissingText(usize),
+    Key(usize, KeymapError),
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::UnterminatedString(line) => {
+                write!(f, "line {}: Unterminated string", line)
+            }
+            ReplayError::MissingText(line) => {
+                write!(f, "line {}: Expected a quoted string after `type`", line)
+            }
+            ReplayError::Key(line, err) => write!(f, "line {}: {}", line, err),
+        }
+    }
+}
+
+/// Parse a replay script: statements separated by `;` or newlines,
+/// either `type "text"` or a key chord like `down` or `ctrl+enter`.
+/// Lines starting with `#` are comments.
+pub fn parse(script: &str) -> Result<Vec<Step>, ReplayError> {
+    let mut steps = vec![];
+    for (index, line) in script.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        for statement in split_statements(line, line_number)? {
+            steps.push(parse_statement(&statement, line_number)?);
+        }
+    }
+    Ok(steps)
+}
+
+fn parse_statement(statement: &str, line: usize) -> Result<Step, ReplayError> {
+    match statement.strip_prefix("type") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            let text = rest.trim();
+            let text = text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+                .ok_or(ReplayError::MissingText(line))?;
+            Ok(Step::Type(unescape(text)))
+        }
+        _ => statement
+            .parse()
+            .map(Step::Key)
+            .map_err(|err| ReplayError::Key(line, err)),
+    }
+}
+
+// Split a line at the semicolons outside of strings, dropping empty statements
+fn split_statements(line: &str, line_number: usize) -> Result<Vec<String>, ReplayError> {
+    let mut statements = vec![];
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => {
+                statements.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    if in_string {
+        return Err(ReplayError::UnterminatedString(line_number));
+    }
+    statements.push(current);
+
+    Ok(statements
+        .into_iter()
+        .map(|statement| statement.trim().to_string())
+        .filter(|statement| !statement.is_empty())
+        .collect())
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::keyboard::{KeyCode, Modifiers};
+
+    use super::{parse, ReplayError, Step};
+    use crate::core::keymap::KeyChord;
+    use crate::s;
+
+    fn key(key: KeyCode, modifiers: Modifiers) -> Step {
+        Step::Key(KeyChord { key, modifiers })
+    }
+
+    #[test]
+    fn parses_statements() {
+        let script =
+            "type \"doc\"; down\n# pick the second match\n\nctrl+enter;type \"a; \\\"b\\\"\"";
+
+        assert_eq!(
+            parse(script).unwrap(),
+            vec![
+                Step::Type(s!("doc")),
+                key(KeyCode::Down, Modifiers::empty()),
+                key(KeyCode::Enter, Modifiers::CTRL),
+                Step::Type(s!("a; \"b\"")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        assert!(matches!(
+            parse("down\ntype \"doc"),
+            Err(ReplayError::UnterminatedString(2))
+        ));
+        assert!(matches!(
+            parse("type doc"),
+            Err(ReplayError::MissingText(1))
+        ));
+        assert!(matches!(parse("down; typo"), Err(ReplayError::Key(1, _))));
+    }
+}
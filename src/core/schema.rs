@@ -0,0 +1,290 @@
+use serde_json::{json, Value};
+
+use crate::core::commands::KNOWN_ICONS;
+
+// Constants -------------------------------------------------------------------
+
+const SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// Appended to the descriptions of the fields that are expanded by `template::expand`
+const TEMPLATE: &str =
+    "Expands ~, $VAR, ${VAR:-default} and {parent.id}, {parent.title}, {parent.value} and {parent.subtitle} of the level the command is listed in";
+
+// Impl ------------------------------------------------------------------------
+
+/// JSON Schema of the user-friendly command format read from `--json` and from
+/// the output of `next` actions.
+pub fn command_schema() -> Value {
+    let templated = |description: &str| format!("{}. {}", description, TEMPLATE);
+    let kind = json!({
+        "description": "Shell command to run, `$__COMMAND_VALUE` holds the value of the command. {parent.*} placeholders expand to single-quoted words, the shell expands ~ and variables itself",
+        "oneOf": [
+            { "type": "string" },
+            {
+                "type": "object",
+                "properties": { "shell": { "type": "string" } }
+            }
+        ]
+    });
+    let action = json!({
+        "description": "exit: run detached and exit, next: show the output as the next menu, print: exit and print the output",
+        "enum": ["exit", "next", "print", "Exit", "Next", "Print"],
+        "default": "exit"
+    });
+
+    json!({
+        "$schema": SCHEMA_DRAFT,
+        "title": "iced_prompt command",
+        "$ref": "#/$defs/command",
+        "$defs": {
+            "command": {
+                "type": "object",
+                "required": ["value"],
+                "additionalProperties": false,
+                "properties": {
+                    "id": {
+                        "description": "Unique identifier of the command within the tree",
+                        "type": "string"
+                    },
+                    "title": {
+                        "description": templated("Text shown in the list instead of the value"),
+                        "type": "string"
+                    },
+                    "value": {
+                        "description": templated("Text shown in the list and passed to the shell command"),
+                        "type": "string"
+                    },
+                    "shell": kind,
+                    "kind": kind,
+                    "provider": {
+                        "description": "Built-in generator of the menu opened by a next action, instead of a shell command",
+                        "oneOf": [
+                            { "const": "processes" },
+                            {
+                                "type": "object",
+                                "required": ["process"],
+                                "properties": {
+                                    "process": {
+                                        "type": "object",
+                                        "required": ["pid"],
+                                        "properties": { "pid": { "type": "integer" } }
+                                    }
+                                }
+                            },
+                            {
+                                "type": "object",
+                                "required": ["signal"],
+                                "properties": {
+                                    "signal": {
+                                        "type": "object",
+                                        "required": ["pid", "signal"],
+                                        "properties": {
+                                            "pid": { "type": "integer" },
+                                            "signal": {
+                                                "description": "Name of the signal, e.g. SIGTERM",
+                                                "type": "string"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        ]
+                    },
+                    "cwd": {
+                        "description": templated("Working directory of the shell command"),
+                        "type": "string"
+                    },
+                    "icon": {
+                        "description": templated("Icon shown next to the command"),
+                        "anyOf": [
+                            { "enum": KNOWN_ICONS },
+                            { "type": "string", "pattern": "\\$|\\{parent\\." }
+                        ]
+                    },
+                    "section": {
+                        "description": "Heading the command is grouped under in the list",
+                        "type": "string"
+                    },
+                    "subtitle": { "type": "string" },
+                    "accessory": {
+                        "description": "Right-aligned text, e.g. a shortcut, file size or branch",
+                        "type": "string"
+                    },
+                    "badges": {
+                        "type": "array",
+                        "items": {
+                            "oneOf": [
+                                { "type": "string" },
+                                {
+                                    "type": "object",
+                                    "required": ["text"],
+                                    "properties": {
+                                        "text": { "type": "string" },
+                                        "color": {
+                                            "description": "primary, secondary, surface, error or a hex color",
+                                            "type": "string"
+                                        }
+                                    }
+                                }
+                            ]
+                        }
+                    },
+                    "image": {
+                        "description": "Path to a local PNG, JPEG or SVG shown as thumbnail and preview",
+                        "type": "string"
+                    },
+                    "layout": {
+                        "description": "Show the items as a list or as a wrapping grid",
+                        "enum": ["list", "grid"],
+                        "default": "list"
+                    },
+                    "columns": {
+                        "description": "Columns of the grid layout",
+                        "type": "integer",
+                        "minimum": 1
+                    },
+                    "action": action,
+                    "cache": {
+                        "description": "Show the last result of a next action instantly and refresh it in the background",
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "ttl": {
+                                "description": "Seconds a result is shown instantly, forever when missing",
+                                "type": "integer",
+                                "minimum": 0
+                            },
+                            "by_value": {
+                                "description": "Cache a result per value instead of per shell command",
+                                "type": "boolean"
+                            },
+                            "persist": {
+                                "description": "Keep results on disk, so they survive restarts",
+                                "type": "boolean"
+                            }
+                        }
+                    },
+                    "refresh_interval": {
+                        "description": "Seconds after which the menu opened by a next action is generated again",
+                        "type": "integer",
+                        "minimum": 1
+                    },
+                    "items": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/command" }
+                    },
+                    "alt_actions": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/altAction" }
+                    },
+                    "include": {
+                        "description": "Files or globs relative to this file whose commands are appended to the items",
+                        "oneOf": [
+                            { "type": "string" },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    }
+                }
+            },
+            "altAction": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "key": {
+                        "description": "Key chord triggering the action, e.g. ctrl+enter",
+                        "type": "string"
+                    },
+                    "title": { "type": "string" },
+                    "shell": kind,
+                    "kind": kind,
+                    "action": action
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::command_schema;
+    use crate::core::commands::Command;
+
+    fn property_names(schema: &Value, definition: &str) -> Vec<String> {
+        schema["$defs"][definition]["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    // Whether an object of the `schema` definition may have the property `key`
+    fn accepts_key(schema: &Value, key: &str) -> bool {
+        schema["properties"].get(key).is_some() || schema["additionalProperties"] != false
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let schema = command_schema();
+        let command = &schema["$defs"]["command"];
+
+        assert!(accepts_key(command, "value"));
+        assert!(!accepts_key(command, "decription"));
+        assert!(!accepts_key(&schema["$defs"]["altAction"], "value"));
+        assert!(!accepts_key(&command["properties"]["cache"], "tll"));
+    }
+
+    #[test]
+    fn describes_templates_on_their_fields() {
+        let schema = command_schema();
+        let properties = &schema["$defs"]["command"]["properties"];
+        let description = |field: &str| properties[field]["description"].as_str().unwrap();
+
+        for field in ["title", "value", "cwd", "icon"] {
+            assert!(description(field).contains("${VAR:-default}"), "{}", field);
+        }
+        assert!(description("shell").contains("single-quoted"));
+        assert!(!description("section").contains("{parent"));
+    }
+
+    #[test]
+    fn describes_every_serialized_field() {
+        let data = r#"{
+    "id": "files",
+    "title": "Files",
+    "value": "~",
+    "shell": "ls",
+    "provider": "processes",
+    "cwd": "~/Projects",
+    "icon": "Directory",
+    "section": "Places",
+    "subtitle": "Home",
+    "accessory": "12 items",
+    "badges": ["new"],
+    "image": "~/Pictures/home.png",
+    "layout": "grid",
+    "columns": 4,
+    "action": "next",
+    "cache": { "ttl": 60, "persist": true },
+    "refresh_interval": 5,
+    "items": [{ "value": "child" }],
+    "alt_actions": [
+        { "key": "ctrl+enter", "title": "Reveal", "shell": "xdg-open", "action": "print" }
+    ]
+}"#;
+        let command: Command = serde_json::from_str(data).unwrap();
+        let json = serde_json::to_value(&command).unwrap();
+        let schema = command_schema();
+
+        let command_fields = property_names(&schema, "command");
+        for key in json.as_object().unwrap().keys() {
+            assert!(command_fields.contains(key), "missing field {}", key);
+        }
+        let alt_action_fields = property_names(&schema, "altAction");
+        for key in json["alt_actions"][0].as_object().unwrap().keys() {
+            assert!(alt_action_fields.contains(key), "missing field {}", key);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::commands::Command;
+use crate::core::history::{History, HistoryEntry, ViewState};
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+struct SessionLevel {
+    command: Command,
+    query: String,
+    // Index of the selected item, as item ids are generated anew on load
+    selected: Option<usize>,
+    scroll_offset: f32,
+    // The `next` command whose output is the level, see `HistoryEntry::generator`
+    generator: Option<Command>,
+}
+
+/// History stack persisted between launches, see `--resume`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Session {
+    // Levels starting at the root
+    levels: Vec<SessionLevel>,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SessionError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl Session {
+    pub fn from_history(history: &History) -> Session {
+        let entries: Vec<&HistoryEntry> = history.history.iter().collect();
+        let levels = entries
+            .into_iter()
+            .rev()
+            .map(|entry| SessionLevel {
+                command: entry.command.clone(),
+                query: entry.view.query.clone(),
+                selected: entry
+                    .view
+                    .selected
+                    .and_then(|id| entry.command.index_of_item_with_id(id)),
+                scroll_offset: entry.view.scroll_offset,
+                generator: entry.generator.clone(),
+            })
+            .collect();
+        Session { levels }
+    }
+
+    pub fn into_history(self) -> History {
+        self.levels
+            .into_iter()
+            .fold(History::default(), |history, level| {
+                let view = ViewState {
+                    selected: level
+                        .selected
+                        .and_then(|index| level.command.items.order.get(index).copied()),
+                    query: level.query,
+                    scroll_offset: level.scroll_offset,
+                };
+                let history = match level.generator {
+                    Some(generator) => history.push_generated(level.command, generator),
+                    None => history.push(level.command),
+                };
+                history.with_view(view)
+            })
+    }
+
+    /// Whether the session was saved for a menu starting at `root`.
+    pub fn has_root(&self, root: &Command) -> bool {
+        // Compare the serialized form, as item ids differ between loads
+        let as_json = |command: &Command| serde_json::to_value(command).ok();
+        match self.levels.first() {
+            Some(level) => as_json(&level.command) == as_json(root),
+            None => false,
+        }
+    }
+
+    /// Location of the session file in the XDG state directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| dir.join("iced_prompt").join("session.json"))
+    }
+
+    pub fn load(path: &Path) -> Result<Session, SessionError> {
+        let json_string =
+            fs::read_to_string(path).map_err(|err| SessionError::Io(path.to_path_buf(), err))?;
+        serde_json::from_str(&json_string)
+            .map_err(|err| SessionError::Parse(path.to_path_buf(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        let io_error = |err| SessionError::Io(path.to_path_buf(), err);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let json_string = serde_json::to_string(self)
+            .map_err(|err| SessionError::Parse(path.to_path_buf(), err))?;
+        fs::write(path, json_string).map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use crate::core::commands::Command;
+    use crate::core::history::{History, ViewState};
+
+    fn menu() -> Command {
+        let data = include_str!("../../data/user_friendly_simple.json");
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn restores_levels_and_view_state() {
+        let root = menu();
+        let selected = root.items.order[1];
+        let history = History::default()
+            .push(root.clone())
+            .with_view(ViewState {
+                query: "ls".to_string(),
+                selected: Some(selected),
+                scroll_offset: 10.,
+            })
+            .push_generated(
+                Command {
+                    value: "~".to_string(),
+                    ..Command::default()
+                },
+                Command {
+                    value: "Files".to_string(),
+                    ..Command::default()
+                },
+            );
+
+        let json = serde_json::to_string(&Session::from_history(&history)).unwrap();
+        let session: Session = serde_json::from_str(&json).unwrap();
+
+        assert!(session.has_root(&menu()));
+        let restored = session.into_history();
+        assert_eq!(restored.head_generator().unwrap().value, "Files");
+        let restored = restored.pop_with_minimum();
+        let head = restored.head().unwrap();
+        let view = restored.head_view().unwrap();
+        assert_eq!(restored.depth(), 1);
+        assert_eq!(view.query, "ls");
+        assert_eq!(view.scroll_offset, 10.);
+        assert_eq!(view.selected, Some(head.items.order[1]));
+    }
+
+    #[test]
+    fn matches_only_the_saved_root() {
+        let session = Session::from_history(&History::default().push(menu()));
+
+        assert!(session.has_root(&menu()));
+        assert!(!session.has_root(&Command::default()));
+        assert!(!Session::default().has_root(&menu()));
+    }
+}
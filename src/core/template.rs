@@ -0,0 +1,168 @@
+use crate::core::commands::Command;
+
+// Constants -------------------------------------------------------------------
+
+// Variables the prompt sets for the shell commands it runs, expanding them
+// from the environment of the prompt itself would shadow the real value
+const RESERVED_VARIABLES: &[&str] = &["__COMMAND_VALUE"];
+
+const PARENT_PREFIX: &str = "{parent.";
+
+// Impl ------------------------------------------------------------------------
+
+/// Expand `~`, `$VAR`, `${VAR}` and `${VAR:-default}` from the environment and
+/// `{parent.id}`, `{parent.title}`, `{parent.value}` and `{parent.subtitle}` from
+/// the level `parent` the expanded field belongs to.
+/// Unknown variables and placeholders are left as they are.
+pub fn expand(input: &str, parent: Option<&Command>) -> String {
+    expand_with_env(input, parent, |name| std::env::var(name).ok())
+}
+
+// `expand` with the variables looked up by `env`
+fn expand_with_env<E>(input: &str, parent: Option<&Command>, env: E) -> String
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let home_dir = || dirs::home_dir().map(|dir| dir.to_string_lossy().into_owned());
+    let expanded =
+        shellexpand::full_with_context_no_errors(input, home_dir, |name| match RESERVED_VARIABLES
+            .contains(&name)
+        {
+            true => None,
+            false => env(name),
+        });
+    match parent {
+        Some(parent) => expand_parent(&expanded, parent, str::to_string),
+        None => expanded.into_owned(),
+    }
+}
+
+/// Expand the `{parent.*}` placeholders of the shell command `input` to single-quoted words,
+/// so values like file names can't inject commands. The shell expands `~` and variables itself.
+pub fn expand_shell(input: &str, parent: Option<&Command>) -> String {
+    match parent {
+        Some(parent) => expand_parent(input, parent, quote),
+        None => input.to_string(),
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Whether `input` contains anything `expand` could replace.
+pub fn is_template(input: &str) -> bool {
+    input.starts_with('~') || input.contains('$') || input.contains(PARENT_PREFIX)
+}
+
+fn expand_parent(input: &str, parent: &Command, substitute: fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find(PARENT_PREFIX) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let field = rest[PARENT_PREFIX.len()..]
+            .find('}')
+            .map(|end| &rest[PARENT_PREFIX.len()..PARENT_PREFIX.len() + end]);
+        let value = field.and_then(|field| match field {
+            "id" => Some(parent.id.clone().unwrap_or_default()),
+            "title" => Some(parent.title.clone().unwrap_or(parent.value.clone())),
+            "value" => Some(parent.value.clone()),
+            "subtitle" => Some(parent.subtitle.clone().unwrap_or_default()),
+            _ => None,
+        });
+        match (field, value) {
+            (Some(field), Some(value)) => {
+                result.push_str(&substitute(&value));
+                rest = &rest[PARENT_PREFIX.len() + field.len() + 1..];
+            }
+            // Keep unknown placeholders
+            _ => {
+                result.push_str(PARENT_PREFIX);
+                rest = &rest[PARENT_PREFIX.len()..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, expand_shell, expand_with_env};
+    use crate::core::commands::Command;
+    use crate::s;
+
+    #[test]
+    fn expands_environment_variables() {
+        std::env::set_var("ICED_PROMPT_TEMPLATE_EXPANDS", "docs");
+        let home = dirs::home_dir().unwrap();
+
+        assert_eq!(expand("$ICED_PROMPT_TEMPLATE_EXPANDS/a", None), "docs/a");
+        assert_eq!(expand("${ICED_PROMPT_TEMPLATE_EXPANDS}s", None), "docss");
+        assert_eq!(expand("${ICED_PROMPT_UNSET:-fallback}", None), "fallback");
+        assert_eq!(
+            expand("echo $ICED_PROMPT_UNSET", None),
+            "echo $ICED_PROMPT_UNSET"
+        );
+        assert_eq!(expand("~/notes", None), format!("{}/notes", home.display()));
+        assert_eq!(expand("a ~ b", None), "a ~ b");
+    }
+
+    #[test]
+    fn keeps_variables_set_for_shell_commands() {
+        let env = |_: &str| Some(s!("outer"));
+        assert_eq!(
+            expand_with_env("echo $__COMMAND_VALUE $OTHER", None, env),
+            "echo $__COMMAND_VALUE outer"
+        );
+    }
+
+    #[test]
+    fn expands_parent_fields() {
+        let parent = Command {
+            value: s!("api"),
+            subtitle: Some(s!("Container")),
+            ..Command::default()
+        };
+
+        assert_eq!(
+            expand("docker logs {parent.value}", Some(&parent)),
+            "docker logs api"
+        );
+        assert_eq!(
+            expand("{parent.title}: {parent.subtitle}", Some(&parent)),
+            "api: Container"
+        );
+        assert_eq!(expand("{parent.id}", Some(&parent)), "");
+        assert_eq!(
+            expand("{parent.unknown} {parent.value", Some(&parent)),
+            "{parent.unknown} {parent.value"
+        );
+        assert_eq!(expand("{parent.value}", None), "{parent.value}");
+    }
+
+    #[test]
+    fn quotes_parent_fields_in_shell_commands() {
+        std::env::set_var("ICED_PROMPT_TEMPLATE_QUOTES", "docs");
+        let parent = Command {
+            value: s!("x; rm -rf ~"),
+            subtitle: Some(s!("it's")),
+            ..Command::default()
+        };
+
+        assert_eq!(
+            expand_shell("ls {parent.value}", Some(&parent)),
+            "ls 'x; rm -rf ~'"
+        );
+        assert_eq!(
+            expand_shell("echo {parent.subtitle}", Some(&parent)),
+            r"echo 'it'\''s'"
+        );
+        assert_eq!(
+            expand_shell("echo '$ICED_PROMPT_TEMPLATE_QUOTES' ~", Some(&parent)),
+            "echo '$ICED_PROMPT_TEMPLATE_QUOTES' ~"
+        );
+    }
+}
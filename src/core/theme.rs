@@ -1,4 +1,8 @@
+use std::str::FromStr;
+
 use iced::{color, Color};
+use serde::de::Deserializer;
+use serde::Deserialize;
 
 #[derive(Default, Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Theme {
@@ -8,29 +12,43 @@ pub enum Theme {
     Light,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct BaseColors {
+    #[serde(deserialize_with = "deserialize_color")]
     pub background: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub foreground: Color,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct NormalColors {
+    #[serde(deserialize_with = "deserialize_color")]
     pub primary: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub secondary: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub surface: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub error: Color,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct BrightColors {
+    #[serde(deserialize_with = "deserialize_color")]
     pub primary: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub secondary: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub surface: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub error: Color,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct ColorPalette {
     pub base: BaseColors,
     pub normal: NormalColors,
@@ -99,6 +117,71 @@ impl Theme {
     }
 }
 
+impl Default for ColorPalette {
+    fn default() -> Self {
+        Theme::default().palette()
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl ColorPalette {
+    /// Resolve a palette color name (primary, secondary, surface, error) or a hex color.
+    pub fn named_color(&self, name: &str) -> Option<Color> {
+        match name {
+            "primary" => Some(self.bright.primary),
+            "secondary" => Some(self.bright.secondary),
+            "surface" => Some(self.bright.surface),
+            "error" => Some(self.bright.error),
+            _ => parse_hex_color(name),
+        }
+    }
+}
+
+/// Parse a hex color like "#282a36", "#fff" or "#282a36cc".
+pub fn parse_hex_color(hex: &str) -> Option<Color> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let channel =
+        |i: usize, len: usize| u8::from_str_radix(digits.get(i * len..(i + 1) * len)?, 16).ok();
+
+    match digits.len() {
+        3 => {
+            let expand = |i| channel(i, 1).map(|c| c * 17);
+            Some(Color::from_rgb8(expand(0)?, expand(1)?, expand(2)?))
+        }
+        6 => Some(Color::from_rgb8(
+            channel(0, 2)?,
+            channel(1, 2)?,
+            channel(2, 2)?,
+        )),
+        8 => Some(Color::from_rgba8(
+            channel(0, 2)?,
+            channel(1, 2)?,
+            channel(2, 2)?,
+            channel(3, 2)? as f32 / 255.,
+        )),
+        _ => None,
+    }
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = String::deserialize(deserializer)?;
+    parse_hex_color(&hex)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid hex color `{}`", hex)))
+}
+
 impl std::fmt::Display for Theme {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -112,3 +195,54 @@ impl std::fmt::Display for Theme {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use iced::{color, Color};
+
+    use super::{parse_hex_color, ColorPalette, Theme};
+
+    #[test]
+    fn parses_theme_names() {
+        assert_eq!("dark".parse(), Ok(Theme::Dark));
+        assert_eq!("Lupin".parse(), Ok(Theme::Lupin));
+        assert_eq!("solarized".parse::<Theme>(), Err(()));
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_hex_color("#282a36"), Some(color!(0x282a36)));
+        assert_eq!(parse_hex_color("fff"), Some(Color::WHITE));
+        assert_eq!(
+            parse_hex_color("#00000080"),
+            Some(Color::from_rgba8(0, 0, 0, 128. / 255.))
+        );
+        assert_eq!(parse_hex_color("#12345"), None);
+        assert_eq!(parse_hex_color("#gggggg"), None);
+    }
+
+    #[test]
+    fn deserializes_palette() {
+        let data = r##"
+[base]
+background = "#282a36"
+foreground = "#353746"
+
+[normal]
+primary = "#58406F"
+secondary = "#386e50"
+surface = "#a2a4a3"
+error = "#A13034"
+
+[bright]
+primary = "#bd94f9"
+secondary = "#49eb7a"
+surface = "#f4f8f3"
+error = "#E63E6D"
+"##;
+
+        let palette: ColorPalette = toml::from_str(data).unwrap();
+
+        assert_eq!(palette, Theme::Lupin.palette());
+    }
+}
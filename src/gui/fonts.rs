@@ -6,3 +6,12 @@ pub const ROBOTO: iced::Font = iced::Font {
     monospaced: false,
     stretch: iced::font::Stretch::Normal,
 };
+
+/// Font with the given family name, falls back to Roboto.
+pub fn font_from_family(family: Option<&str>) -> iced::Font {
+    match family {
+        // Fonts are referenced by a static name and live for the whole program
+        Some(name) => iced::Font::with_name(Box::leak(name.to_string().into_boxed_str())),
+        None => ROBOTO,
+    }
+}
@@ -7,5 +7,14 @@ pub static DIRECTORY: Lazy<svg::Handle> =
 pub static FILE: Lazy<svg::Handle> =
     Lazy::new(|| svg::Handle::from_memory(include_bytes!("../../icons/file.svg").to_vec()));
 
+/// Icon referenced by the `icon` name of a command.
+pub fn by_name(name: &str) -> Option<svg::Handle> {
+    match name {
+        "Directory" => Some(DIRECTORY.clone()),
+        "File" => Some(FILE.clone()),
+        _ => None,
+    }
+}
+
 pub static LOADER: Lazy<svg::Handle> =
     Lazy::new(|| svg::Handle::from_memory(include_bytes!("../../icons/loader.svg").to_vec()));
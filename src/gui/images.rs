@@ -0,0 +1,209 @@
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use iced::advanced::{image, svg};
+use lru::LruCache;
+
+// Constants -------------------------------------------------------------------
+
+// Thumbnails are small, so many of them fit in memory
+const THUMBNAIL_CACHE_SIZE: usize = 256;
+const PREVIEW_CACHE_SIZE: usize = 8;
+
+// Items whose thumbnails are loaded from the scroll position on,
+// well below the cache size so they don't evict each other
+pub const THUMBNAIL_BATCH_SIZE: usize = 64;
+
+// Longest side of the decoded images, in pixels
+const THUMBNAIL_SIZE: u32 = 96;
+const PREVIEW_SIZE: u32 = 2048;
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ImageSize {
+    // Shown in rows and grid cells
+    Thumbnail,
+    // Shown next to the list for the selected item
+    Preview,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImageData {
+    Raster(image::Handle),
+    Vector(svg::Handle),
+}
+
+#[derive(Debug, Clone)]
+pub enum CachedImage {
+    Loading,
+    Loaded(ImageData),
+    Failed,
+}
+
+/// Decoded images by path, least recently loaded images are dropped first.
+#[derive(Debug)]
+pub struct ImageCache {
+    thumbnails: LruCache<PathBuf, CachedImage>,
+    previews: LruCache<PathBuf, CachedImage>,
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        let size = |size: usize| NonZeroUsize::new(size).unwrap();
+        ImageCache {
+            thumbnails: LruCache::new(size(THUMBNAIL_CACHE_SIZE)),
+            previews: LruCache::new(size(PREVIEW_CACHE_SIZE)),
+        }
+    }
+}
+
+impl ImageCache {
+    fn cache(&self, size: ImageSize) -> &LruCache<PathBuf, CachedImage> {
+        match size {
+            ImageSize::Thumbnail => &self.thumbnails,
+            ImageSize::Preview => &self.previews,
+        }
+    }
+
+    fn cache_mut(&mut self, size: ImageSize) -> &mut LruCache<PathBuf, CachedImage> {
+        match size {
+            ImageSize::Thumbnail => &mut self.thumbnails,
+            ImageSize::Preview => &mut self.previews,
+        }
+    }
+
+    /// The loaded image at `path`, without marking it as recently used,
+    /// `start_loading` does so for the images that are shown.
+    pub fn get(&self, path: &Path, size: ImageSize) -> Option<&ImageData> {
+        match self.cache(size).peek(path) {
+            Some(CachedImage::Loaded(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Mark `path` as loading, returns false when it is already cached or loading.
+    /// Cached images are marked as recently used.
+    pub fn start_loading(&mut self, path: &Path, size: ImageSize) -> bool {
+        let cache = self.cache_mut(size);
+        if cache.get(path).is_some() {
+            return false;
+        }
+        cache.put(path.to_path_buf(), CachedImage::Loading);
+        true
+    }
+
+    pub fn insert(&mut self, path: PathBuf, size: ImageSize, result: Result<ImageData, String>) {
+        let image = match result {
+            Ok(data) => CachedImage::Loaded(data),
+            Err(err) => {
+                eprintln!("Failed to load {}: {}", path.display(), err);
+                CachedImage::Failed
+            }
+        };
+        self.cache_mut(size).put(path, image);
+    }
+}
+
+/// Path of the `image` field of a command.
+pub fn image_path(image: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(image).as_ref())
+}
+
+/// Read and decode the image at `path` on a blocking thread.
+pub async fn load(path: PathBuf, size: ImageSize) -> Result<ImageData, String> {
+    async_std::task::spawn_blocking(move || decode(&path, size)).await
+}
+
+fn decode(path: &Path, size: ImageSize) -> Result<ImageData, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    if path.extension().and_then(|e| e.to_str()) == Some("svg") {
+        return Ok(ImageData::Vector(svg::Handle::from_memory(bytes)));
+    }
+
+    let decoded = ::image::load_from_memory(&bytes).map_err(|err| err.to_string())?;
+    let max_size = match size {
+        ImageSize::Thumbnail => THUMBNAIL_SIZE,
+        ImageSize::Preview => PREVIEW_SIZE,
+    };
+    let decoded = match decoded.width().max(decoded.height()) > max_size {
+        true => decoded.thumbnail(max_size, max_size),
+        false => decoded,
+    };
+    let rgba = decoded.to_rgba8();
+    Ok(ImageData::Raster(image::Handle::from_pixels(
+        rgba.width(),
+        rgba.height(),
+        rgba.into_raw(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{decode, ImageCache, ImageData, ImageSize, PREVIEW_CACHE_SIZE};
+    use crate::utils::temp_dir::TempDir;
+
+    #[test]
+    fn tracks_loading_images() {
+        let mut cache = ImageCache::default();
+        let path = Path::new("wallpaper.png");
+
+        assert!(cache.start_loading(path, ImageSize::Thumbnail));
+        assert!(!cache.start_loading(path, ImageSize::Thumbnail));
+        assert!(cache.get(path, ImageSize::Thumbnail).is_none());
+        assert!(cache.start_loading(path, ImageSize::Preview));
+
+        cache.insert(
+            path.to_path_buf(),
+            ImageSize::Thumbnail,
+            Err("broken".into()),
+        );
+        assert!(cache.get(path, ImageSize::Thumbnail).is_none());
+        assert!(!cache.start_loading(path, ImageSize::Thumbnail));
+    }
+
+    #[test]
+    fn keeps_images_that_are_shown_again() {
+        let mut cache = ImageCache::default();
+        let paths: Vec<PathBuf> = (0..=PREVIEW_CACHE_SIZE)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+        let first = paths[0].as_path();
+
+        for path in &paths[..PREVIEW_CACHE_SIZE] {
+            cache.start_loading(path, ImageSize::Preview);
+        }
+        // Shown again, so the second image is dropped instead
+        assert!(!cache.start_loading(first, ImageSize::Preview));
+        cache.start_loading(&paths[PREVIEW_CACHE_SIZE], ImageSize::Preview);
+
+        assert!(!cache.start_loading(first, ImageSize::Preview));
+        assert!(cache.start_loading(&paths[1], ImageSize::Preview));
+    }
+
+    #[test]
+    fn decodes_thumbnails_and_svgs() {
+        let svg = decode(Path::new("icons/file.svg"), ImageSize::Thumbnail);
+        assert!(matches!(svg, Ok(ImageData::Vector(_))));
+
+        let dir = TempDir::new("thumbnail");
+        let png = dir.path().join("thumbnail.png");
+        ::image::RgbaImage::new(400, 200).save(&png).unwrap();
+        match decode(&png, ImageSize::Thumbnail) {
+            Ok(ImageData::Raster(handle)) => match handle.data() {
+                iced::advanced::image::Data::Rgba { width, height, .. } => {
+                    assert_eq!((*width, *height), (96, 48))
+                }
+                other => panic!("Expected pixels, got {:?}", other),
+            },
+            other => panic!("Expected a raster image, got {:?}", other),
+        }
+
+        assert!(decode(Path::new("missing.png"), ImageSize::Preview).is_err());
+    }
+}
@@ -2,18 +2,21 @@ pub mod colors;
 pub mod components;
 pub mod fonts;
 pub mod icons;
+pub mod images;
+pub mod replay;
+pub mod state;
 pub mod style;
+pub mod watcher;
 
 use core::fmt;
-use iced::keyboard::{KeyCode, Modifiers};
 use iced::theme::Theme;
-use iced::widget::scrollable::{AbsoluteOffset, RelativeOffset, Viewport};
+use iced::widget::scrollable::AbsoluteOffset;
 use iced::widget::{
-    button, column, container, horizontal_rule, row, scrollable, svg, text, text_input, Row, Space,
+    button, column, container, horizontal_rule, image, row, scrollable, svg, text, text_input, Row,
+    Space,
 };
-use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
 
 use iced::window::{self, Level};
 use iced::{font, subscription, Alignment, Event, Padding};
@@ -23,11 +26,20 @@ use iced::{Length, Settings, Subscription};
 use once_cell::sync::Lazy;
 use uuid::Uuid;
 
-use crate::core::commands::{ActionKind, Command, CommandResultError, SIMPLE_CMD_HEIGHT};
+use crate::core::cache::ResultCache;
+use crate::core::commands::{Command, Layout};
+use crate::core::config::{Config, FontConfig};
+use crate::core::context::ExecutionContext;
+use crate::core::geometry::Geometry;
 use crate::core::history::History;
+use crate::core::keymap::Keymap;
+use crate::core::loader::Sources;
+use crate::core::theme::ColorPalette;
 use fonts::ROBOTO_BYTES;
+use images::{ImageData, ImageSize};
+use state::{Effect, Message, Selection, State, INPUT_PADDING};
 use style::DEFAULT_BORDER_RADIUS;
-use style::{footer_container_style, get_svg_style};
+use style::{badge_container_style, footer_container_style, get_svg_style, Themed};
 
 static SCROLLABLE_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
 static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
@@ -47,25 +59,54 @@ impl fmt::Display for AppError {
     }
 }
 
-pub fn main(cmd: Command) -> Result<Command, AppError> {
+/// Commands file to reload on changes, see `--watch`.
+#[derive(Debug, Clone, Default)]
+pub struct WatchedCommands {
+    pub path: PathBuf,
+    // The file at `path`, its includes and include patterns
+    pub sources: Sources,
+}
+
+/// Run the prompt on `history`, saving it to `session_path` on exit when given.
+pub fn main(
+    history: History,
+    config: Config,
+    session_path: Option<PathBuf>,
+    watched: Option<WatchedCommands>,
+) -> Result<(Command, ExecutionContext), AppError> {
     let result = Arc::new(Mutex::new(None));
+    let geometry = config.window.detect_geometry();
+    let position = match geometry.position {
+        Some((x, y)) => window::Position::Specific(x, y),
+        None => window::Position::Centered,
+    };
 
     let window_result = LoadingState::run(Settings {
         window: window::Settings {
-            size: (700, 500),
-            position: window::Position::Centered,
+            size: (geometry.width, geometry.height),
+            position,
             transparent: true,
             decorations: false,
             resizable: false,
             level: Level::AlwaysOnTop,
             ..window::Settings::default()
         },
+        default_font: fonts::font_from_family(config.font.family.as_deref()),
+        default_text_size: config.font.size,
         flags: ApplicationFlags {
-            cmd,
+            history,
+            session_path,
+            watched,
+            keymap: config.keymap,
+            palette: config.palette,
+            font: config.font,
+            font_data: config.font_data,
+            geometry,
             result: result.clone(),
         },
-        default_font: fonts::ROBOTO,
         antialiasing: true,
+        // Closing the window quits through `Message::Exit`, which saves the session
+        exit_on_close_request: false,
         ..Settings::default()
     });
 
@@ -81,103 +122,146 @@ pub fn main(cmd: Command) -> Result<Command, AppError> {
     }
 }
 
-#[derive(Debug, Default)]
-enum Selection {
-    #[default]
-    Initial,
-    Selected(Uuid),
-}
-
-#[derive(Debug, Default)]
-struct State {
-    input_value: String,
-    history: History,
-    filter: Option<Vec<Uuid>>,
-    selection: Selection,
-    scrollable_offset: AbsoluteOffset,
-    result: Arc<Mutex<Option<Command>>>,
-    jobs: HashMap<Uuid, Instant>,
-}
-
 #[derive(Debug)]
 enum LoadingState {
     Loaded(State),
 }
 
-#[derive(Debug, Clone)]
-enum Message {
-    InputChanged(String),
-    ToggleFullscreen(window::Mode),
-    Exit(i32),
-    Select(i32),
-    Submit(Option<Uuid>),
-    OnScroll(Viewport),
-    HistoryBackwards,
-    FontLoaded(Result<(), font::Error>),
-    PushHistory(Command),
-}
-
 impl State {
-    fn push_history(&mut self, command: Command) -> iced::Command<Message> {
-        let history = &self.history;
-        let next_history = history.clone().push(command);
-        self.jobs.clear();
-        self.navigate(next_history)
-    }
-    fn navigate(&mut self, history: History) -> iced::Command<Message> {
-        self.filter = None;
-        self.selection = Selection::Initial;
-        self.input_value = "".to_string();
-        self.history = history;
-
-        iced::Command::batch(vec![
-            text_input::focus(INPUT_ID.clone()),
-            scrollable::snap_to(SCROLLABLE_ID.clone(), RelativeOffset::START),
-        ])
+    fn item_style(&self, idx: usize, id: &Uuid) -> style::Button {
+        match (&self.selection, idx) {
+            (Selection::Initial, 0) => style::Button::Focused,
+            (Selection::Selected(selected_id), _) if selected_id == id => style::Button::Focused,
+            _ => style::Button::Primary,
+        }
     }
 
-    fn selected_command(&mut self, option_id: Option<Uuid>) -> Option<(Uuid, Command)> {
-        let history = &self.history;
-        let filter = &self.filter;
-
-        let opt_cmds = history.head();
-
-        opt_cmds.as_ref()?;
-        let cmds = opt_cmds.unwrap();
-
-        let id = if let Some(id) = option_id {
-            id
-        } else {
-            match &self.selection {
-                Selection::Initial => {
-                    let order = filter.clone().unwrap_or(cmds.items.order);
-                    order[0]
+    /// Rows of grid cells for the items of `cmds`.
+    fn grid_rows(&self, cmds: &Command) -> Vec<Element<'_, Message>> {
+        let metrics = self.font.row_metrics();
+        let columns = cmds.columns();
+        let running = self.running_items();
+
+        let cells: Vec<Element<_>> = cmds.map_filter_items(|idx, id, cmd| {
+            let title = cmd.title.as_deref().unwrap_or(&cmd.value);
+            let mut cell = column![].spacing(4).align_items(Alignment::Center);
+            match running.get(id) {
+                Some(started) => cell = cell.push(components::spinner::circle(12., *started)),
+                None => {
+                    let thumbnail = cmd.image.as_deref().and_then(|image| {
+                        self.image_element(image, ImageSize::Thumbnail, 48., 48.)
+                    });
+                    if let Some(thumbnail) = thumbnail {
+                        cell = cell.push(thumbnail);
+                    } else if let Some(handle) = cmd.icon.as_deref().and_then(icons::by_name) {
+                        cell = cell.push(
+                            svg(handle)
+                                .width(32.)
+                                .height(32.)
+                                .style(get_svg_style(self.palette)),
+                        );
+                    }
                 }
-                Selection::Selected(selected_id) => *selected_id,
             }
-        };
+            cell = cell.push(text(title).size(self.font.footer_size));
+
+            let element = button(
+                container(cell)
+                    .height(metrics.cell_height)
+                    .width(Length::Fill)
+                    .center_x()
+                    .center_y(),
+            )
+            .style(iced::theme::Button::Custom(Box::new(Themed::new(
+                self.item_style(idx, id),
+                self.palette,
+            ))))
+            .width(Length::FillPortion(1))
+            .on_press(Message::Submit(Some(*id)))
+            .into();
+            Some(element)
+        });
+
+        let mut rows = vec![];
+        let mut cells = cells.into_iter().peekable();
+        while cells.peek().is_some() {
+            let mut row = Row::new().spacing(metrics.spacing);
+            for _ in 0..columns {
+                row = match cells.next() {
+                    Some(cell) => row.push(cell),
+                    // Keep the cells of the last row as wide as the others
+                    None => row.push(Space::new(Length::FillPortion(1), Length::Shrink)),
+                };
+            }
+            rows.push(row.into());
+        }
+        rows
+    }
 
-        let hash_map = &cmds.items.items;
-        hash_map.get(&id).map(|x| (id, x.clone()))
+    /// The loaded `image` of a command, or `None` while it is loading.
+    fn image_element(
+        &self,
+        image_path: &str,
+        size: ImageSize,
+        width: impl Into<Length>,
+        height: impl Into<Length>,
+    ) -> Option<Element<'_, Message>> {
+        let element = match self.images.get(&images::image_path(image_path), size)? {
+            ImageData::Raster(handle) => image(handle.clone()).width(width).height(height).into(),
+            ImageData::Vector(handle) => svg(handle.clone()).width(width).height(height).into(),
+        };
+        Some(element)
     }
 }
 
-struct ApplicationStyle {}
+struct ApplicationStyle {
+    palette: ColorPalette,
+}
 impl iced::application::StyleSheet for ApplicationStyle {
     type Style = iced::Theme;
 
     fn appearance(&self, _style: &Self::Style) -> iced::application::Appearance {
         iced::application::Appearance {
             background_color: iced::Color::TRANSPARENT,
-            text_color: iced::Color::WHITE,
+            text_color: self.palette.bright.surface,
+        }
+    }
+}
+
+/// Turn an effect of `State::update` into a command of the runtime.
+fn perform(effect: Effect) -> iced::Command<Message> {
+    match effect {
+        Effect::FocusInput => text_input::focus(INPUT_ID.clone()),
+        Effect::ScrollTo(y) => {
+            scrollable::scroll_to(SCROLLABLE_ID.clone(), AbsoluteOffset { x: 0., y })
+        }
+        Effect::ResizeWindow(width, height) => window::resize(iced::Size::new(width, height)),
+        Effect::Execute(id, command, context) => {
+            iced::Command::perform(command.generate(Some(*context)), move |cmd| {
+                Message::Executed(id, cmd.map_err(|err| err.to_string()))
+            })
+        }
+        Effect::LoadImage(path, size) => {
+            iced::Command::perform(images::load(path.clone(), size), move |result| {
+                Message::ImageLoaded(path, size, result)
+            })
         }
+        Effect::CloseWindow => window::close(),
+        Effect::Exit(exit_code) => std::process::exit(exit_code),
     }
 }
 
 #[derive(Default)]
 struct ApplicationFlags {
-    cmd: Command,
-    result: Arc<Mutex<Option<Command>>>,
+    history: History,
+    session_path: Option<PathBuf>,
+    watched: Option<WatchedCommands>,
+    keymap: Keymap,
+    palette: ColorPalette,
+    font: FontConfig,
+    font_data: Option<Vec<u8>>,
+    geometry: Geometry,
+    result: Arc<Mutex<Option<(Command, ExecutionContext)>>>,
 }
 
 impl Application for LoadingState {
@@ -191,18 +275,26 @@ impl Application for LoadingState {
     }
 
     fn new(flags: ApplicationFlags) -> (LoadingState, iced::Command<Message>) {
-        let state = State {
-            history: History::default().push(flags.cmd),
+        let mut state = State {
             result: flags.result.clone(),
+            session_path: flags.session_path,
+            watched: flags.watched,
+            keymap: flags.keymap,
+            palette: flags.palette,
+            font: flags.font,
+            geometry: flags.geometry,
+            cache: ResultCache::new(ResultCache::default_dir()),
             ..State::default()
         };
-        (
-            LoadingState::Loaded(state),
-            iced::Command::batch(vec![
-                font::load(ROBOTO_BYTES).map(Message::FontLoaded),
-                text_input::focus(INPUT_ID.clone()),
-            ]),
-        )
+        let navigate = state.navigate(flags.history).into_iter().map(perform);
+
+        let mut commands = vec![font::load(ROBOTO_BYTES).map(Message::FontLoaded)];
+        commands.extend(navigate);
+        if let Some(font_data) = flags.font_data {
+            commands.push(font::load(font_data).map(Message::FontLoaded));
+        }
+
+        (LoadingState::Loaded(state), iced::Command::batch(commands))
     }
 
     fn title(&self) -> String {
@@ -210,192 +302,137 @@ impl Application for LoadingState {
     }
 
     fn update(&mut self, message: Message) -> iced::Command<Message> {
-        match self {
-            LoadingState::Loaded(state) => match message {
-                Message::OnScroll(viewport) => {
-                    state.scrollable_offset = viewport.absolute_offset();
-                    iced::Command::none()
-                }
-                Message::HistoryBackwards => {
-                    let prev_history = state.history.clone().pop_with_minimum();
-                    state.navigate(prev_history)
-                }
-                Message::PushHistory(command) => state.push_history(command),
-                Message::InputChanged(value) => {
-                    if value.is_empty() {
-                        state.filter = None;
-                    } else {
-                        state.filter = state
-                            .history
-                            .head()
-                            .map(|cmd| Command::filter_items_by_value(&cmd, &value));
-                    }
-                    state.input_value = value;
-                    state.selection = Selection::Initial;
-
-                    scrollable::snap_to(SCROLLABLE_ID.clone(), RelativeOffset::START)
-                }
-                Message::Select(amount) => {
-                    let selected_command_and_index = match state.history.head() {
-                        Some(cmds) => {
-                            let filtered_cmds = match &state.filter {
-                                Some(order) => cmds.with_order(order.clone()),
-                                _ => cmds,
-                            };
-
-                            let selection_index: usize = match state.selection {
-                                Selection::Initial => 0,
-                                Selection::Selected(id) => {
-                                    let i = &filtered_cmds.index_of_item_with_id(id).unwrap_or(0);
-                                    *i
-                                }
-                            };
-
-                            // Shift the index by the given `amount`
-                            let cmds_len = &filtered_cmds.items.order.len();
-                            let next_index: usize =
-                                num::clamp(selection_index as i32 + amount, 0, *cmds_len as i32)
-                                    as usize;
-
-                            let scroll_offset = filtered_cmds.scroll_offset_at_index(next_index);
-
-                            filtered_cmds
-                                .get_child_command_by_index(next_index)
-                                .map(|(id, _)| (id, scroll_offset))
-                        }
-                        _ => None,
-                    };
-
-                    let next_selection = match &selected_command_and_index {
-                        Some((id, _)) => Selection::Selected(*id),
-                        None => Selection::Initial,
-                    };
-
-                    state.selection = next_selection;
-
-                    match &selected_command_and_index {
-                        None => scrollable::snap_to(SCROLLABLE_ID.clone(), RelativeOffset::START),
-                        Some((_, scroll_offset)) => scrollable::scroll_to(
-                            SCROLLABLE_ID.clone(),
-                            AbsoluteOffset {
-                                x: 0.0,
-                                y: *scroll_offset,
-                            },
-                        ),
-                    }
-                }
-                Message::Submit(maybe_id) => {
-                    match &state.selected_command(maybe_id) {
-                        Some((id, command)) => {
-                            match command.action {
-                                // Next: Try to push result on the history stack
-                                ActionKind::Next => {
-                                    let command_for_async = command.clone();
-                                    state.jobs.insert(*id, Instant::now());
-
-                                    iced::Command::perform(
-                                        async { command_for_async.execute() },
-                                        |io_output| {
-                                            let cmd: Result<Command, CommandResultError> =
-                                                io_output.and_then(|s| Command::parse(&s));
-                                            match cmd {
-                                                Ok(c) => Message::PushHistory(c),
-                                                Err(err) => {
-                                                    println!("{:#?}", err);
-                                                    std::process::exit(1);
-                                                }
-                                            }
-                                        },
-                                    )
-                                }
-                                // Close window & save command so it can be further processed
-                                _ => {
-                                    let mut result = state.result.lock().unwrap();
-                                    *result = Some(command.clone());
-
-                                    window::close()
-                                }
-                            }
-                        }
-                        None => iced::Command::none(),
-                    }
-                }
-                Message::Exit(exit_code) => std::process::exit(exit_code),
-                _ => iced::Command::none(),
-            },
-        }
+        let LoadingState::Loaded(state) = self;
+        iced::Command::batch(state.update(message).into_iter().map(perform))
     }
 
-    fn view(&self) -> Element<Message> {
-        use crate::gui::style::{default_container_style, Button, Rule, TextInput};
+    fn view(&self) -> Element<'_, Message> {
+        use crate::gui::style::{default_container_style, Button, Rule, Scrollable, TextInput};
 
-        let _default_state = State::default();
         let LoadingState::Loaded(state) = self;
         let input_value = &state.input_value;
         let history = &state.history;
-        let selection = &state.selection;
-
-        let current_cmds = history.head().unwrap_or_default();
-        let order: &Vec<Uuid> = &state.filter.clone().unwrap_or(current_cmds.items.order);
-
-        let items = order
-            .iter()
-            .enumerate()
-            .map(|(idx, id)| {
-                let cmd = current_cmds.items.items.get(id).unwrap();
-                let title = cmd.title.as_deref().unwrap_or(&cmd.value);
-                let icon = &cmd.icon;
-
-                let button_style = match (selection, idx) {
-                    (Selection::Initial, 0) => Button::Focused,
-                    (Selection::Selected(selected_id), _) if selected_id == id => Button::Focused,
-                    _ => Button::Primary,
-                };
 
-                let text_value = text(title).line_height(1.25);
+        let no_level = Command::default();
+        let current_cmds = state.level().unwrap_or(&no_level);
+        let order: &Vec<Uuid> = &state
+            .filter
+            .clone()
+            .unwrap_or(current_cmds.items.order.clone());
+        let visible_cmds = current_cmds.with_order(order.clone());
+        let metrics = state.font.row_metrics();
+        let running = state.running_items();
+
+        let items = match visible_cmds.layout {
+            Layout::Grid => state.grid_rows(&visible_cmds),
+            Layout::List => order
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, id)| {
+                    let cmd = current_cmds.items.items.get(id).unwrap();
+                    let title = cmd.title.as_deref().unwrap_or(&cmd.value);
+                    let icon = &cmd.icon;
+
+                    let button_style = state.item_style(idx, id);
+
+                    let title_value = text(title).line_height(1.25);
+                    let text_value: Element<_> = match &cmd.subtitle {
+                        Some(subtitle) => column![
+                            title_value,
+                            container(text(subtitle).size(state.font.footer_size))
+                                .style(footer_container_style(state.palette)),
+                        ]
+                        .into(),
+                        None => title_value.into(),
+                    };
 
-                let icon_element = match icon {
-                    Some(icon_string) => match icon_string.as_str() {
-                        "Directory" => Some(icons::DIRECTORY.clone()),
-                        "File" => Some(icons::FILE.clone()),
-                        _ => None,
-                    },
-                    _ => None,
-                }
-                .map(|svg_icon| svg(svg_icon).width(20.).height(20.).style(get_svg_style()));
-
-                #[allow(clippy::option_map_or_none)]
-                let is_processing = state
-                    .jobs
-                    .get(id)
-                    .map_or(None, |t| Some(components::spinner::circle(7.5, *t)));
-
-                let mut row = Row::new();
-                if let Some(icon_el) = icon_element {
-                    row = row.push(icon_el).push(text_value).spacing(5);
-                } else {
+                    // Thumbnails fill the height of the row, icons are shown until they are loaded
+                    let thumbnail_size = cmd.row_content_height(&metrics);
+                    let thumbnail = cmd.image.as_deref().and_then(|image| {
+                        state.image_element(
+                            image,
+                            ImageSize::Thumbnail,
+                            thumbnail_size,
+                            thumbnail_size,
+                        )
+                    });
+                    let icon_element = thumbnail.or_else(|| {
+                        let svg_icon = icon.as_deref().and_then(icons::by_name)?;
+                        Some(
+                            svg(svg_icon)
+                                .width(20.)
+                                .height(20.)
+                                .style(get_svg_style(state.palette))
+                                .into(),
+                        )
+                    });
+
+                    #[allow(clippy::option_map_or_none)]
+                    let is_processing = running
+                        .get(id)
+                        .map_or(None, |t| Some(components::spinner::circle(7.5, *t)));
+
+                    let mut row = Row::new().spacing(5);
+                    if let Some(icon_el) = icon_element {
+                        row = row.push(icon_el);
+                    }
                     row = row.push(text_value);
-                }
 
-                if let Some(loading_text) = is_processing {
-                    row = row
-                        .push(Space::new(Length::Fill, Length::Shrink))
-                        .push(container(loading_text).padding(iced::Padding::from([0, 4])))
-                }
+                    for badge in &cmd.badges {
+                        let color = badge
+                            .color
+                            .as_deref()
+                            .and_then(|name| state.palette.named_color(name))
+                            .unwrap_or(state.palette.bright.surface);
+                        row = row.push(
+                            container(text(&badge.text).size(state.font.footer_size))
+                                .style(badge_container_style(state.palette, color))
+                                .padding(iced::Padding::from([1, 5])),
+                        );
+                    }
 
-                let button_content = row.align_items(Alignment::Center);
+                    row = row.push(Space::new(Length::Fill, Length::Shrink));
+                    if let Some(accessory) = &cmd.accessory {
+                        row = row.push(
+                            container(text(accessory).size(state.font.footer_size))
+                                .style(footer_container_style(state.palette)),
+                        );
+                    }
+                    if let Some(loading_text) = is_processing {
+                        row = row.push(container(loading_text).padding(iced::Padding::from([0, 4])))
+                    }
 
-                button(
-                    container(button_content)
-                        .height(SIMPLE_CMD_HEIGHT)
-                        .center_y(),
-                )
-                .style(iced::theme::Button::Custom(Box::new(button_style)))
-                .width(Length::Fill)
-                .on_press(Message::Submit(Some(*id)))
-                .into()
-            })
-            .collect();
+                    let button_content = row.align_items(Alignment::Center);
+
+                    let item = button(
+                        container(button_content)
+                            .height(cmd.row_content_height(&metrics))
+                            .center_y(),
+                    )
+                    .style(iced::theme::Button::Custom(Box::new(Themed::new(
+                        button_style,
+                        state.palette,
+                    ))))
+                    .width(Length::Fill)
+                    .on_press(Message::Submit(Some(*id)))
+                    .into();
+
+                    // Headers are not part of the order, so the selection skips them
+                    match visible_cmds.section_header(idx) {
+                        Some(section) => {
+                            let header = container(text(section).size(state.font.footer_size))
+                                .style(footer_container_style(state.palette))
+                                .height(metrics.header_height)
+                                .align_y(iced::alignment::Vertical::Bottom)
+                                .padding(iced::Padding::from([0, 5]))
+                                .into();
+                            vec![header, item]
+                        }
+                        None => vec![item],
+                    }
+                })
+                .collect(),
+        };
 
         let cmds_column = column(items)
             .spacing(1)
@@ -410,7 +447,7 @@ impl Application for LoadingState {
         let content: Element<_> = match cmds_column {
             Some(el) => scrollable(row![el])
                 .on_scroll(Message::OnScroll)
-                .style(crate::gui::style::Scrollable::Default)
+                .style(Themed::new(Scrollable::Default, state.palette))
                 .height(Length::Fill)
                 .direction(scrollable::Direction::Vertical(
                     scrollable::Properties::new()
@@ -429,20 +466,76 @@ impl Application for LoadingState {
                 .into(),
         };
 
+        // Preview of the selected item next to the list
+        let preview = state
+            .selected_command(None)
+            .and_then(|(_, cmd)| cmd.image)
+            .and_then(|image| {
+                state.image_element(&image, ImageSize::Preview, Length::Fill, Length::Fill)
+            });
+        let content: Element<_> = match preview {
+            Some(preview) => row![
+                container(content).width(Length::FillPortion(3)),
+                container(preview)
+                    .width(Length::FillPortion(2))
+                    .height(Length::Fill)
+                    .padding(iced::Padding::from([
+                        0.,
+                        10. + DEFAULT_BORDER_RADIUS,
+                        0.,
+                        0.
+                    ]))
+                    .center_x()
+                    .center_y(),
+            ]
+            .into(),
+            None => content,
+        };
+
         let input = column![
             text_input("Your prompt", input_value)
                 .id(INPUT_ID.clone())
-                .style(TextInput::Default)
+                .style(Themed::new(TextInput::Default, state.palette))
                 .on_input(Message::InputChanged)
-                .padding(Padding::from([15., DEFAULT_BORDER_RADIUS + 10.]))
-                .size(15.),
-            horizontal_rule(1).style(Rule::Default),
+                .padding(Padding::from([INPUT_PADDING, DEFAULT_BORDER_RADIUS + 10.]))
+                .size(state.font.input_size),
+            horizontal_rule(1).style(Themed::new(Rule::Default, state.palette)),
         ];
 
+        // Breadcrumbs of all levels, the ancestors link to their level
+        let depth = history.depth();
+        let mut breadcrumbs = Row::new().spacing(2).align_items(Alignment::Center);
+        for (idx, level) in history.levels().enumerate() {
+            let title =
+                text(level.title.as_deref().unwrap_or(&level.value)).size(state.font.footer_size);
+            if idx + 1 == depth {
+                breadcrumbs = breadcrumbs.push(title);
+            } else {
+                breadcrumbs = breadcrumbs
+                    .push(
+                        button(title)
+                            .padding(iced::Padding::from([2, 4]))
+                            .style(iced::theme::Button::Custom(Box::new(Themed::new(
+                                Button::Breadcrumb,
+                                state.palette,
+                            ))))
+                            .on_press(Message::HistoryJump(idx + 1)),
+                    )
+                    .push(text("›").size(state.font.footer_size));
+            }
+        }
+
+        // Notifications are shown at the right of the breadcrumbs
+        if let Some(notification) = &state.notification {
+            breadcrumbs = breadcrumbs
+                .push(Space::new(Length::Fill, Length::Shrink))
+                .push(text(notification).size(state.font.footer_size));
+        }
+
         let footer: Element<_> = column![
-            horizontal_rule(1).style(Rule::Default),
-            container(text(current_cmds.value).size(13))
-                .style(footer_container_style())
+            horizontal_rule(1).style(Themed::new(Rule::Default, state.palette)),
+            container(breadcrumbs)
+                .style(footer_container_style(state.palette))
                 .center_y()
                 .height(Length::Fill)
                 .padding(iced::Padding::from([0, 10])),
@@ -463,38 +556,41 @@ impl Application for LoadingState {
                 .height(Length::Fill)
                 .center_x()
                 .padding(1)
-                .style(default_container_style())
+                .style(default_container_style(state.palette))
                 .center_y(),
         )
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        subscription::events_with(|event, _status| match event {
+        // Key chords are resolved against the keymap in `update`,
+        // as `events_with` only accepts a non-capturing function
+        let events = subscription::events_with(|event, _status| match event {
             Event::Keyboard(iced::keyboard::Event::KeyPressed {
                 modifiers,
                 key_code,
-            }) => match (key_code, modifiers) {
-                (KeyCode::Enter, modifier) => match modifier {
-                    _ => Some(Message::Submit(None)),
-                },
-                (KeyCode::Backspace, Modifiers::SHIFT) => Some(Message::HistoryBackwards),
-                (KeyCode::Tab, Modifiers::SHIFT) => Some(Message::HistoryBackwards),
-                (KeyCode::Escape, _) => Some(Message::Exit(0)),
-                (KeyCode::Up, Modifiers::SHIFT) => {
-                    Some(Message::ToggleFullscreen(window::Mode::Fullscreen))
-                }
-                (KeyCode::Down, Modifiers::SHIFT) => {
-                    Some(Message::ToggleFullscreen(window::Mode::Windowed))
-                }
-                (KeyCode::Up, _) | (KeyCode::P, Modifiers::CTRL) => Some(Message::Select(-1)),
-                (KeyCode::Down, _) | (KeyCode::N, Modifiers::CTRL) => Some(Message::Select(1)),
-                _ => None,
-            },
+            }) => Some(Message::KeyPressed(key_code, modifiers)),
+            Event::Window(window::Event::CloseRequested) => Some(Message::Exit(0)),
             _ => None,
-        })
+        });
+
+        let LoadingState::Loaded(state) = self;
+        let mut subscriptions = vec![events];
+        if let Some(watched) = &state.watched {
+            subscriptions.push(
+                watcher::watch(watched.path.clone(), watched.sources.clone())
+                    .map(Message::CommandsReloaded),
+            );
+        }
+        if let Some(interval) = state.refresh_interval() {
+            subscriptions.push(iced::time::every(interval).map(|_| Message::RefreshLevel));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn style(&self) -> iced::theme::Application {
-        iced::theme::Application::Custom(Box::new(ApplicationStyle {}))
+        let LoadingState::Loaded(state) = self;
+        iced::theme::Application::Custom(Box::new(ApplicationStyle {
+            palette: state.palette,
+        }))
     }
 }
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+use async_std::task::block_on;
+
+use crate::core::commands::Command;
+use crate::core::config::Config;
+use crate::core::history::History;
+use crate::core::replay::Step;
+use crate::gui::state::{Effect, Message, State};
+
+/// Run the prompt on `history` without a window, feeding it the `steps` of a replay script.
+/// Returns the submitted command, or the selected one when the script ends before a submit,
+/// `None` when the prompt was quit and an error when nothing is selected at the end.
+pub fn run(history: History, config: Config, steps: &[Step]) -> Result<Option<Command>, String> {
+    let mut state = State {
+        keymap: config.keymap,
+        font: config.font,
+        ..State::default()
+    };
+    let mut pending: VecDeque<Effect> = state.navigate(history).into();
+
+    let mut steps = steps.iter();
+    loop {
+        // Effects can send further messages, so they are performed in order before the next step
+        if let Some(effect) = pending.pop_front() {
+            match effect {
+                Effect::Execute(id, command, context) => {
+                    let result =
+                        block_on(command.generate(Some(*context))).map_err(|err| err.to_string());
+                    pending.extend(state.update(Message::Executed(id, result)));
+                }
+                Effect::CloseWindow => {
+                    let result = state.result.lock().unwrap().clone();
+                    return Ok(result.map(|(command, _)| command));
+                }
+                Effect::Exit(_) => return Ok(None),
+                _ => (),
+            }
+            continue;
+        }
+
+        let message = match steps.next() {
+            Some(Step::Type(text)) => {
+                Message::InputChanged(format!("{}{}", state.input_value, text))
+            }
+            Some(Step::Key(chord)) => Message::KeyPressed(chord.key, chord.modifiers),
+            None => {
+                return match state.selected_command(None) {
+                    Some((_, command)) => Ok(Some(command)),
+                    None => Err("The script ended without a selected item".to_string()),
+                }
+            }
+        };
+        pending = state.update(message).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::core::commands::Command;
+    use crate::core::config::Config;
+    use crate::core::history::History;
+    use crate::core::keymap::{Keymap, Preset};
+    use crate::core::replay;
+
+    fn replay(json_str: &str, script: &str) -> Result<Option<Command>, String> {
+        let history = History::default().push(Command::parse(json_str).unwrap());
+        let config = Config {
+            keymap: Keymap::new(Preset::Default, []).unwrap(),
+            ..Config::default()
+        };
+        run(history, config, &replay::parse(script).unwrap())
+    }
+
+    #[test]
+    fn runs_scripts_through_next_actions() {
+        let data = r#"{ "value": "Menu", "items": [
+            { "value": "Docs", "shell": "echo '{ \"value\": \"Docs\", \"items\": [{ \"value\": \"a\" }, { \"value\": \"b\" }] }'", "action": "next" },
+            { "value": "Downloads" }
+        ] }"#;
+
+        let submitted = replay(data, "type \"doc\"; enter; down; enter").unwrap();
+        assert_eq!(submitted.unwrap().value, "b");
+
+        let selected = replay(data, "down").unwrap();
+        assert_eq!(selected.unwrap().value, "Downloads");
+
+        assert_eq!(replay(data, "escape"), Ok(None));
+        assert!(replay(data, "type \"nothing\"").is_err());
+    }
+
+    #[test]
+    fn keeps_running_after_a_failed_next_action() {
+        let data = r#"{ "value": "Menu", "items": [
+            { "value": "Broken", "shell": "exit 1", "action": "next" },
+            { "value": "Downloads" }
+        ] }"#;
+
+        let submitted = replay(data, "enter; down; enter").unwrap();
+        assert_eq!(submitted.unwrap().value, "Downloads");
+    }
+}
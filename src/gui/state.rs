@@ -0,0 +1,1168 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use iced::font;
+use iced::keyboard::{KeyCode, Modifiers};
+use iced::widget::scrollable::Viewport;
+use uuid::Uuid;
+
+use crate::core::cache::ResultCache;
+use crate::core::commands::{ActionKind, CachePolicy, Command, Layout};
+use crate::core::config::FontConfig;
+use crate::core::context::ExecutionContext;
+use crate::core::geometry::Geometry;
+use crate::core::history::{History, ViewState};
+use crate::core::keymap::{Action, KeyChord, Keymap};
+use crate::core::providers::Provider;
+use crate::core::session::Session;
+use crate::core::theme::ColorPalette;
+use crate::gui::images::{self, ImageCache, ImageData, ImageSize};
+use crate::gui::watcher;
+use crate::gui::WatchedCommands;
+
+// Constants -------------------------------------------------------------------
+
+// Amount of items skipped by the page-up and page-down actions
+const PAGE_SIZE: i32 = 10;
+
+// Height of everything around the list except the input: rules, spacing and footer
+const CHROME_HEIGHT: f32 = 58.;
+
+// Vertical padding of the input
+pub const INPUT_PADDING: f32 = 15.;
+
+// Types -----------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+pub enum Selection {
+    #[default]
+    Initial,
+    Selected(Uuid),
+}
+
+/// A running `next` command.
+#[derive(Debug, Clone)]
+pub struct Job {
+    // Item that started the job, shows the progress
+    pub item: Option<Uuid>,
+    pub title: String,
+    pub command: Command,
+    pub started: Instant,
+    // Id of the level the item belongs to, results are only pushed while it is shown
+    pub level: Uuid,
+    pub kind: JobKind,
+    // Key and policy to cache the result with
+    pub cache: Option<(String, CachePolicy)>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum JobKind {
+    // Push the result as a new level
+    Push,
+    // Replace the cached result shown in `level`
+    Refresh,
+}
+
+/// Everything the prompt shows, updated by `Message`s without touching the window.
+#[derive(Debug, Default)]
+pub struct State {
+    pub input_value: String,
+    pub history: History,
+    // The head of `history` with the templates of its items expanded, set by `navigate`
+    pub level: Option<Command>,
+    pub filter: Option<Vec<Uuid>>,
+    pub selection: Selection,
+    pub scroll_offset: f32,
+    // The submitted command and the context it is run with
+    pub result: Arc<Mutex<Option<(Command, ExecutionContext)>>>,
+    // Running jobs by job id
+    pub jobs: HashMap<Uuid, Job>,
+    // Shown in the footer until the next navigation
+    pub notification: Option<String>,
+    pub keymap: Keymap,
+    pub geometry: Geometry,
+    pub palette: ColorPalette,
+    pub font: FontConfig,
+    // Where to save the history on exit, see `--resume`
+    pub session_path: Option<PathBuf>,
+    pub watched: Option<WatchedCommands>,
+    pub images: ImageCache,
+    pub cache: ResultCache,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    InputChanged(String),
+    KeyPressed(KeyCode, Modifiers),
+    Exit(i32),
+    // Move the selection by rows
+    Select(i32),
+    // Move the selection within a row of a grid
+    SelectColumn(i32),
+    Submit(Option<Uuid>),
+    SubmitWith(ActionKind),
+    ActionMenu,
+    OnScroll(Viewport),
+    HistoryBackwards,
+    HistoryForward,
+    HistoryJump(usize),
+    FontLoaded(Result<(), font::Error>),
+    CommandsReloaded(watcher::Reload),
+    ImageLoaded(PathBuf, ImageSize, Result<ImageData, String>),
+    // Parsed output of the job with the id
+    Executed(Uuid, Result<Command, String>),
+    // Generate the current level again, see `State::refresh_interval`
+    RefreshLevel,
+}
+
+/// Side effects of an update, performed by the application.
+#[derive(Debug, Clone)]
+pub enum Effect {
+    FocusInput,
+    // Absolute vertical offset of the list
+    ScrollTo(f32),
+    ResizeWindow(u32, u32),
+    // Run the command of the job with the id, reported back with `Message::Executed`
+    Execute(Uuid, Box<Command>, Box<ExecutionContext>),
+    LoadImage(PathBuf, ImageSize),
+    // The result is stored in `State::result`
+    CloseWindow,
+    Exit(i32),
+}
+
+// Impl ------------------------------------------------------------------------
+
+impl State {
+    /// Apply `message` and return the effects it causes.
+    pub fn update(&mut self, message: Message) -> Vec<Effect> {
+        match message {
+            Message::OnScroll(viewport) => {
+                self.scroll_offset = viewport.absolute_offset().y;
+                self.load_images()
+            }
+            Message::HistoryBackwards => {
+                let prev_history = self.saved_history().pop_with_minimum();
+                self.navigate(prev_history)
+            }
+            Message::HistoryForward => {
+                if !self.history.can_go_forward() {
+                    return vec![];
+                }
+                let next_history = self.saved_history().go_forward();
+                self.navigate(next_history)
+            }
+            Message::HistoryJump(depth) => {
+                if depth >= self.history.depth() {
+                    return vec![];
+                }
+                let ancestor_history = self.saved_history().truncate(depth);
+                self.navigate(ancestor_history)
+            }
+            Message::Executed(id, result) => match self.jobs.remove(&id) {
+                Some(job) => self.job_finished(job, result),
+                None => vec![],
+            },
+            Message::RefreshLevel => self.refresh_level(),
+            Message::CommandsReloaded(Ok((command, sources))) => {
+                if let Some(watched) = &mut self.watched {
+                    watched.sources = sources;
+                }
+                let next_history = self.saved_history().replace_root(command);
+                self.navigate(next_history)
+            }
+            Message::CommandsReloaded(Err(err)) => {
+                let message = format!("Failed to reload the commands: {}", err);
+                eprintln!("{}", message);
+                self.notification = Some(message);
+                vec![]
+            }
+            Message::ImageLoaded(path, size, result) => {
+                self.images.insert(path, size, result);
+                vec![]
+            }
+            Message::InputChanged(value) => {
+                self.filter = self.filter_by(&value);
+                self.input_value = value;
+                self.selection = Selection::Initial;
+                self.scroll_offset = 0.;
+
+                let mut effects = vec![Effect::ScrollTo(0.)];
+                effects.extend(self.fit_window());
+                effects.extend(self.load_images());
+                effects
+            }
+            Message::Select(amount) => self.select_rows(amount),
+            Message::SelectColumn(amount) => {
+                let is_grid = self
+                    .visible_command()
+                    .is_some_and(|cmds| cmds.layout == Layout::Grid);
+                match is_grid {
+                    true => self.select_by(amount),
+                    // Leave left and right to the cursor of the input
+                    false => vec![],
+                }
+            }
+            Message::Submit(maybe_id) => self.submit(maybe_id, None),
+            Message::SubmitWith(action) => self.submit(None, Some(action)),
+            Message::KeyPressed(key, modifiers) => {
+                if let Some(effects) = self.key_pressed(KeyChord { key, modifiers }) {
+                    return effects;
+                }
+                match self.keymap.action(key, modifiers) {
+                    Some(action) => self.update(Message::from(action)),
+                    None => vec![],
+                }
+            }
+            Message::ActionMenu => {
+                let action_menu = self
+                    .selected_command(None)
+                    .and_then(|(_, command)| command.action_menu());
+                match action_menu {
+                    Some(menu) => self.push_history(menu),
+                    None => vec![],
+                }
+            }
+            Message::Exit(exit_code) => {
+                self.save_session();
+                vec![Effect::Exit(exit_code)]
+            }
+            Message::FontLoaded(_) => vec![],
+        }
+    }
+
+    fn push_history(&mut self, command: Command) -> Vec<Effect> {
+        let next_history = self.saved_history().push(command);
+        self.navigate(next_history)
+    }
+
+    fn push_generated(&mut self, command: Command, generator: Command) -> Vec<Effect> {
+        let next_history = self.saved_history().push_generated(command, generator);
+        self.navigate(next_history)
+    }
+
+    /// Generate the current level again, unless it is already being refreshed.
+    fn refresh_level(&mut self) -> Vec<Effect> {
+        let (Some(level), Some(generator)) =
+            (self.history.head_id(), self.history.head_generator())
+        else {
+            return vec![];
+        };
+        let is_refreshing = self
+            .jobs
+            .values()
+            .any(|job| job.kind == JobKind::Refresh && job.level == level);
+        match is_refreshing {
+            true => vec![],
+            false => {
+                // The generator runs from the level below, as when it was submitted
+                let parent = self.history.clone().pop();
+                let query = parent.head_view().unwrap_or_default().query;
+                let context = ExecutionContext::new(generator.clone(), &query, &parent);
+                self.start_job(None, generator.clone(), JobKind::Refresh, context)
+            }
+        }
+    }
+
+    /// How often the current level is generated again, see `Command::refresh_interval`.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        let seconds = self.history.head_generator()?.refresh_interval?;
+        Some(Duration::from_secs(seconds.max(1)))
+    }
+
+    /// Push the result of `job` when its level is still shown, otherwise only notify about it.
+    fn job_finished(&mut self, job: Job, result: Result<Command, String>) -> Vec<Effect> {
+        if let (Ok(command), Some((key, policy))) = (&result, &job.cache) {
+            self.cache.insert(key.clone(), command.clone(), policy);
+        }
+
+        let is_current = self.history.head_id() == Some(job.level);
+        match (job.kind, is_current, result) {
+            (JobKind::Refresh, true, Ok(command)) => {
+                let refreshed_history = self.saved_history().replace_head(command);
+                self.navigate(refreshed_history)
+            }
+            (JobKind::Refresh, false, Ok(_)) => vec![],
+            // The cached result stays, as it is still useful
+            (JobKind::Refresh, _, Err(err)) => {
+                eprintln!("{}", err);
+                vec![]
+            }
+            (JobKind::Push, true, Ok(command)) => {
+                let returns_to_parent = job
+                    .command
+                    .provider
+                    .as_ref()
+                    .is_some_and(Provider::returns_to_parent);
+                match returns_to_parent && self.history.depth() > 1 {
+                    // E.g. the processes listed again after a signal replace the signal menu
+                    true => {
+                        let parent_history = self.saved_history().pop().replace_head(command);
+                        self.navigate(parent_history)
+                    }
+                    false => self.push_generated(command, job.command),
+                }
+            }
+            // The other items can still be used, e.g. after a provider failed on an exited process
+            (JobKind::Push, true, Err(err)) => {
+                eprintln!("{}", err);
+                self.notification = Some(err);
+                vec![]
+            }
+            (JobKind::Push, false, Ok(_)) => {
+                self.notification = Some(format!("{} finished after leaving its menu", job.title));
+                vec![]
+            }
+            (JobKind::Push, false, Err(err)) => {
+                eprintln!("{}", err);
+                self.notification = Some(format!("{} failed", job.title));
+                vec![]
+            }
+        }
+    }
+
+    /// Start times of the jobs started from the current level, by item.
+    pub fn running_items(&self) -> HashMap<Uuid, Instant> {
+        let Some(level) = self.history.head_id() else {
+            return HashMap::new();
+        };
+        self.jobs
+            .values()
+            .filter(|job| job.level == level)
+            .filter_map(|job| Some((job.item?, job.started)))
+            .collect()
+    }
+
+    /// Navigate to the head of `history` and restore its UI state.
+    pub fn navigate(&mut self, history: History) -> Vec<Effect> {
+        let view = history.head_view().unwrap_or_default();
+        self.level = history.head().map(|cmd| cmd.with_expanded_items());
+        self.history = history;
+        self.notification = None;
+
+        self.filter = self.filter_by(&view.query);
+        self.input_value = view.query;
+        self.selection = match view.selected {
+            Some(id)
+                if self
+                    .visible_command()
+                    .and_then(|cmd| cmd.index_of_item_with_id(id))
+                    .is_some() =>
+            {
+                Selection::Selected(id)
+            }
+            _ => Selection::Initial,
+        };
+        self.scroll_offset = view.scroll_offset;
+
+        let mut effects = vec![Effect::FocusInput, Effect::ScrollTo(self.scroll_offset)];
+        effects.extend(self.fit_window());
+        effects.extend(self.load_images());
+        effects
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            query: self.input_value.clone(),
+            selected: match self.selection {
+                Selection::Selected(id) => Some(id),
+                Selection::Initial => None,
+            },
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    /// The history with the UI state of the current level stored in it.
+    fn saved_history(&self) -> History {
+        self.history.clone().with_view(self.view_state())
+    }
+
+    fn save_session(&self) {
+        if let Some(path) = &self.session_path {
+            if let Err(err) = Session::from_history(&self.saved_history()).save(path) {
+                eprintln!("Failed to save session: {}", err);
+            }
+        }
+    }
+
+    fn filter_by(&self, query: &str) -> Option<Vec<Uuid>> {
+        if query.is_empty() {
+            return None;
+        }
+        self.level()
+            .map(|cmd| Command::filter_items_by_value(cmd, query))
+    }
+
+    /// The current level with the templates of its items expanded.
+    pub fn level(&self) -> Option<&Command> {
+        self.level.as_ref()
+    }
+
+    /// The current level with its items in the order they are displayed.
+    pub fn visible_command(&self) -> Option<Command> {
+        let cmds = self.level()?;
+        Some(match &self.filter {
+            Some(order) => cmds.with_order(order.clone()),
+            None => cmds.clone(),
+        })
+    }
+
+    /// Shrink the window height to the visible rows when `max_rows` is configured.
+    fn fit_window(&self) -> Option<Effect> {
+        let (Some(max_rows), Some(cmds)) = (self.geometry.max_rows, self.visible_command()) else {
+            return None;
+        };
+
+        // Keep the space of one row for the "Nothing found" message
+        let metrics = self.font.row_metrics();
+        let rows = cmds.row_count().clamp(1, max_rows.max(1));
+        let rows_height = match cmds.items.order.is_empty() {
+            true => Command::default().command_kind_height(&metrics),
+            false => cmds.scroll_offset_at_index(rows * cmds.columns(), &metrics),
+        };
+        // Text inputs use a line height of 1.3
+        let input_height = self.font.input_size * 1.3 + INPUT_PADDING * 2.;
+        let height = (CHROME_HEIGHT + input_height + rows_height).ceil() as u32;
+
+        Some(Effect::ResizeWindow(
+            self.geometry.width,
+            height.min(self.geometry.height),
+        ))
+    }
+
+    /// Start loading the thumbnails of the items from the scroll position on
+    /// and the preview of the selected item, after the visible items or the selection changed.
+    fn load_images(&mut self) -> Vec<Effect> {
+        let Some(cmds) = self.visible_command() else {
+            return vec![];
+        };
+        let metrics = self.font.row_metrics();
+        let indices: Vec<usize> = (0..cmds.items.order.len()).collect();
+        let first_visible = indices.partition_point(|index| {
+            cmds.scroll_offset_at_index(index + 1, &metrics) <= self.scroll_offset
+        });
+
+        let thumbnails = cmds.items.order[first_visible..]
+            .iter()
+            .take(images::THUMBNAIL_BATCH_SIZE)
+            .filter_map(|id| cmds.items.items.get(id)?.image.clone())
+            .map(|path| (path, ImageSize::Thumbnail));
+        let preview = self
+            .selected_command(None)
+            .and_then(|(_, cmd)| cmd.image)
+            .map(|path| (path, ImageSize::Preview));
+
+        let loads: Vec<(String, ImageSize)> = thumbnails.chain(preview).collect();
+        loads
+            .into_iter()
+            .map(|(path, size)| (images::image_path(&path), size))
+            .filter(|(path, size)| self.images.start_loading(path, *size))
+            .map(|(path, size)| Effect::LoadImage(path, size))
+            .collect()
+    }
+
+    /// Move the selection by `amount` items, clamped to the visible items.
+    fn select_by(&mut self, amount: i32) -> Vec<Effect> {
+        let selected_command_and_index = match self.visible_command() {
+            Some(filtered_cmds) => {
+                let selection_index: usize = match self.selection {
+                    Selection::Initial => 0,
+                    Selection::Selected(id) => {
+                        let i = &filtered_cmds.index_of_item_with_id(id).unwrap_or(0);
+                        *i
+                    }
+                };
+
+                // Shift the index by the given `amount`
+                let last_index = filtered_cmds.items.order.len() as i32 - 1;
+                let next_index: usize = num::clamp(
+                    (selection_index as i32).saturating_add(amount),
+                    0,
+                    last_index.max(0),
+                ) as usize;
+
+                let scroll_offset =
+                    filtered_cmds.scroll_offset_at_index(next_index, &self.font.row_metrics());
+
+                filtered_cmds
+                    .get_child_command_by_index(next_index)
+                    .map(|(id, _)| (id, scroll_offset))
+            }
+            _ => None,
+        };
+
+        let next_selection = match &selected_command_and_index {
+            Some((id, _)) => Selection::Selected(*id),
+            None => Selection::Initial,
+        };
+
+        self.selection = next_selection;
+
+        let mut effects = match &selected_command_and_index {
+            None => vec![Effect::ScrollTo(0.)],
+            Some((_, scroll_offset)) => vec![Effect::ScrollTo(*scroll_offset)],
+        };
+        effects.extend(self.load_images());
+        effects
+    }
+
+    /// Move the selection by `amount` rows, which span several items in a grid.
+    fn select_rows(&mut self, amount: i32) -> Vec<Effect> {
+        let columns = self.visible_command().map_or(1, |cmds| cmds.columns());
+        self.select_by(amount.saturating_mul(columns as i32))
+    }
+
+    pub fn selected_command(&self, option_id: Option<Uuid>) -> Option<(Uuid, Command)> {
+        let filter = &self.filter;
+
+        let cmds = self.level()?;
+
+        let id = if let Some(id) = option_id {
+            id
+        } else {
+            match &self.selection {
+                Selection::Initial => {
+                    let order = filter.as_ref().unwrap_or(&cmds.items.order);
+                    *order.first()?
+                }
+                Selection::Selected(selected_id) => *selected_id,
+            }
+        };
+
+        let hash_map = &cmds.items.items;
+        hash_map.get(&id).map(|x| (id, x.clone()))
+    }
+
+    /// Submit the command with `option_id` or the current selection.
+    /// `action` overrides the `ActionKind` declared by the command.
+    fn submit(&mut self, option_id: Option<Uuid>, action: Option<ActionKind>) -> Vec<Effect> {
+        match self.selected_command(option_id) {
+            Some((id, command)) => {
+                let command = Command {
+                    action: action.unwrap_or(command.action.clone()),
+                    ..command
+                };
+                self.run(id, command)
+            }
+            None => vec![],
+        }
+    }
+
+    /// Run `command` on behalf of the item with `id`, which shows the progress.
+    fn run(&mut self, id: Uuid, command: Command) -> Vec<Effect> {
+        let item = self
+            .selected_command(Some(id))
+            .map_or(command.clone(), |(_, item)| item);
+        let context = ExecutionContext::new(item, &self.input_value, &self.history);
+
+        match command.action {
+            // Next: Try to push result on the history stack
+            ActionKind::Next => {
+                let cached = command
+                    .cache
+                    .as_ref()
+                    .zip(command.cache_key())
+                    .and_then(|(policy, key)| self.cache.get(&key, policy));
+                match cached {
+                    // Show the cached result right away and refresh it in the background
+                    Some(cached) => {
+                        let mut effects = self.push_generated(cached, command.clone());
+                        effects.extend(self.start_job(
+                            Some(id),
+                            command,
+                            JobKind::Refresh,
+                            context,
+                        ));
+                        effects
+                    }
+                    None => self.start_job(Some(id), command, JobKind::Push, context),
+                }
+            }
+            // Close window & save command so it can be further processed
+            _ => {
+                self.save_session();
+
+                let mut result = self.result.lock().unwrap();
+                *result = Some((command, context));
+
+                vec![Effect::CloseWindow]
+            }
+        }
+    }
+
+    /// Run the shell command of `command` on behalf of `item` of the current level.
+    fn start_job(
+        &mut self,
+        item: Option<Uuid>,
+        command: Command,
+        kind: JobKind,
+        context: ExecutionContext,
+    ) -> Vec<Effect> {
+        let Some(level) = self.history.head_id() else {
+            return vec![];
+        };
+        let job_id = Uuid::new_v4();
+        let job = Job {
+            item,
+            title: command.title.clone().unwrap_or(command.value.clone()),
+            command: command.clone(),
+            started: Instant::now(),
+            level,
+            kind,
+            cache: command.cache_key().zip(command.cache.clone()),
+        };
+        self.jobs.insert(job_id, job);
+        vec![Effect::Execute(
+            job_id,
+            Box::new(command),
+            Box::new(context),
+        )]
+    }
+
+    fn key_pressed(&mut self, key: KeyChord) -> Option<Vec<Effect>> {
+        // Alternative actions of the selected item take precedence over the keymap
+        let (id, alt_command) = self
+            .selected_command(None)
+            .and_then(|(id, command)| Some((id, command.alt_action_for_key(&key)?)))?;
+
+        Some(self.run(id, alt_command))
+    }
+}
+
+impl From<Action> for Message {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::SelectNext => Message::Select(1),
+            Action::SelectPrevious => Message::Select(-1),
+            Action::SelectFirst => Message::Select(i32::MIN),
+            Action::SelectLast => Message::Select(i32::MAX),
+            Action::SelectLeft => Message::SelectColumn(-1),
+            Action::SelectRight => Message::SelectColumn(1),
+            Action::PageDown => Message::Select(PAGE_SIZE),
+            Action::PageUp => Message::Select(-PAGE_SIZE),
+            Action::HistoryBack => Message::HistoryBackwards,
+            Action::HistoryForward => Message::HistoryForward,
+            Action::HistoryJump(depth) => Message::HistoryJump(depth),
+            Action::Submit => Message::Submit(None),
+            Action::SubmitExit => Message::SubmitWith(ActionKind::Exit),
+            Action::SubmitPrint => Message::SubmitWith(ActionKind::Print),
+            Action::ActionMenu => Message::ActionMenu,
+            Action::Quit => Message::Exit(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use std::time::Duration;
+
+    use iced::keyboard::{KeyCode, Modifiers};
+    use uuid::Uuid;
+
+    use super::{Effect, Message, State};
+    use crate::core::commands::{ActionKind, Command};
+    use crate::core::context::ExecutionContext;
+    use crate::core::history::History;
+    use crate::core::keymap::{Keymap, Preset};
+    use crate::s;
+
+    // Drives the state like the application does, without a window.
+    // Shell commands are not run, their output is looked up in `outputs`,
+    // jobs without an output keep running until they are finished.
+    #[derive(Default)]
+    struct Harness {
+        state: State,
+        outputs: HashMap<String, String>,
+        executed: Vec<String>,
+        // Contexts of the executed commands
+        contexts: Vec<ExecutionContext>,
+        running: Vec<(Uuid, String)>,
+        closed: bool,
+        exit_code: Option<i32>,
+    }
+
+    impl Harness {
+        fn new(json_str: &str) -> Self {
+            let mut harness = Harness::default();
+            harness.state.keymap = Keymap::new(Preset::Default, []).unwrap();
+            let command = Command::parse(json_str).unwrap();
+            let effects = harness.state.navigate(History::default().push(command));
+            harness.perform(effects);
+            harness
+        }
+
+        fn with_output(mut self, shell: &str, output: &str) -> Self {
+            self.outputs.insert(s!(shell), s!(output));
+            self
+        }
+
+        fn send(&mut self, message: Message) -> &mut Self {
+            let effects = self.state.update(message);
+            self.perform(effects);
+            self
+        }
+
+        fn type_text(&mut self, query: &str) -> &mut Self {
+            self.send(Message::InputChanged(s!(query)))
+        }
+
+        fn press(&mut self, key: KeyCode) -> &mut Self {
+            self.send(Message::KeyPressed(key, Modifiers::empty()))
+        }
+
+        fn perform(&mut self, effects: Vec<Effect>) {
+            for effect in effects {
+                match effect {
+                    Effect::Execute(id, command, context) => {
+                        let shell = command.command_string();
+                        self.executed.push(shell.clone());
+                        self.contexts.push(*context);
+                        match self.outputs.get(&shell) {
+                            Some(output) => self.finish_job(id, &output.clone()),
+                            None => self.running.push((id, shell)),
+                        }
+                    }
+                    Effect::CloseWindow => self.closed = true,
+                    Effect::Exit(exit_code) => self.exit_code = Some(exit_code),
+                    _ => (),
+                }
+            }
+        }
+
+        fn finish_job(&mut self, id: Uuid, output: &str) {
+            let command = Command::parse(output).map_err(|err| format!("{:?}", err));
+            self.send(Message::Executed(id, command));
+        }
+
+        fn finish(&mut self, shell: &str, output: &str) -> &mut Self {
+            let index = self.running.iter().position(|(_, s)| s == shell).unwrap();
+            let (id, _) = self.running.remove(index);
+            self.finish_job(id, output);
+            self
+        }
+
+        fn fail(&mut self, shell: &str, err: &str) -> &mut Self {
+            let index = self.running.iter().position(|(_, s)| s == shell).unwrap();
+            let (id, _) = self.running.remove(index);
+            self.send(Message::Executed(id, Err(s!(err))))
+        }
+
+        fn visible_values(&self) -> Vec<String> {
+            let cmds = self.state.visible_command().unwrap();
+            cmds.items
+                .order
+                .iter()
+                .map(|id| cmds.items.items[id].value.clone())
+                .collect()
+        }
+
+        fn selected_value(&self) -> Option<String> {
+            self.state.selected_command(None).map(|(_, cmd)| cmd.value)
+        }
+
+        fn result(&self) -> Option<Command> {
+            let result = self.state.result.lock().unwrap();
+            result.clone().map(|(command, _)| command)
+        }
+    }
+
+    const APPS: &str = r#"{
+    "value": "Apps",
+    "items": [
+        { "value": "Firefox", "shell": "firefox" },
+        { "value": "Files", "shell": "nautilus" },
+        { "value": "Terminal", "shell": "alacritty" }
+    ]
+}"#;
+
+    #[test]
+    fn typing_filters_and_resets_the_selection() {
+        let mut harness = Harness::new(APPS);
+        assert_eq!(harness.selected_value(), Some(s!("Firefox")));
+
+        harness.type_text("fi");
+        assert_eq!(harness.visible_values(), vec!["Files", "Firefox"]);
+        assert_eq!(harness.selected_value(), Some(s!("Files")));
+
+        harness.send(Message::Select(1));
+        assert_eq!(harness.selected_value(), Some(s!("Firefox")));
+        harness.send(Message::Select(1));
+        assert_eq!(harness.selected_value(), Some(s!("Firefox")));
+
+        harness.type_text("fil");
+        assert_eq!(harness.visible_values(), vec!["Files"]);
+        assert_eq!(harness.selected_value(), Some(s!("Files")));
+
+        harness.type_text("nothing");
+        assert!(harness.visible_values().is_empty());
+        assert_eq!(harness.selected_value(), None);
+    }
+
+    #[test]
+    fn filters_and_submits_expanded_items() {
+        std::env::set_var("ICED_PROMPT_STATE_TEST", "staging");
+        let mut harness = Harness::new(
+            r#"{
+    "value": "Servers",
+    "items": [
+        { "value": "$ICED_PROMPT_STATE_TEST", "shell": "ssh $__COMMAND_VALUE" },
+        { "value": "{parent.value} of ${ICED_PROMPT_UNSET:-production}" }
+    ]
+}"#,
+        );
+        assert_eq!(
+            harness.visible_values(),
+            vec!["staging", "Servers of production"]
+        );
+
+        harness
+            .type_text("stag")
+            .send(Message::SubmitWith(ActionKind::Print));
+        let result = harness.result().unwrap();
+        assert_eq!(result.value, "staging");
+        assert_eq!(result.command_string(), "ssh $__COMMAND_VALUE");
+    }
+
+    #[test]
+    fn submits_the_selected_command() {
+        let mut harness = Harness::new(APPS);
+
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Down)
+            .press(KeyCode::Up);
+        harness.send(Message::SubmitWith(ActionKind::Print));
+
+        assert!(harness.closed);
+        let result = harness.result().unwrap();
+        assert_eq!(result.value, "Files");
+        assert_eq!(result.command_string(), "nautilus");
+        assert_eq!(result.action, ActionKind::Print);
+    }
+
+    #[test]
+    fn pushes_the_output_of_next_actions_and_goes_back() {
+        let mut harness = Harness::new(
+            r#"{ "value": "Git", "items": [
+                { "value": "Status", "shell": "git status" },
+                { "value": "Branches", "shell": "git-branches", "action": "next" }
+            ] }"#,
+        )
+        .with_output(
+            "git-branches",
+            r#"{ "value": "Branches", "items": [{ "value": "main" }, { "value": "dev" }] }"#,
+        );
+
+        harness.type_text("bra").send(Message::Submit(None));
+        assert_eq!(harness.executed, vec!["git-branches"]);
+        assert!(!harness.closed);
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.state.input_value, "");
+        assert_eq!(harness.visible_values(), vec!["main", "dev"]);
+
+        harness.send(Message::HistoryBackwards);
+        assert_eq!(harness.state.history.depth(), 1);
+        assert_eq!(harness.state.input_value, "bra");
+        assert_eq!(harness.visible_values(), vec!["Branches"]);
+
+        harness
+            .send(Message::HistoryForward)
+            .send(Message::Select(1));
+        assert_eq!(harness.selected_value(), Some(s!("dev")));
+    }
+
+    #[test]
+    fn runs_commands_with_the_selection_context() {
+        let mut harness = Harness::new(
+            r#"{ "value": "Git", "items": [
+                { "value": "Branches", "shell": "git-branches", "action": "next" }
+            ] }"#,
+        )
+        .with_output(
+            "git-branches",
+            r#"{ "value": "Branches", "items": [{ "value": "main", "shell": "git switch main" }] }"#,
+        );
+
+        harness.type_text("bra").send(Message::Submit(None));
+        let context = &harness.contexts[0];
+        assert_eq!(context.item.value, "Branches");
+        assert_eq!(context.query, "bra");
+        assert_eq!(context.path, vec!["Git"]);
+
+        harness.send(Message::SubmitWith(ActionKind::Print));
+        let result = harness.state.result.lock().unwrap().clone();
+        let (command, context) = result.unwrap();
+        assert_eq!(command.command_string(), "git switch main");
+        assert_eq!(context.item.value, "main");
+        assert_eq!(context.query, "");
+        assert_eq!(context.path, vec!["Git", "Branches"]);
+        assert_eq!(context.selection, vec![context.item.clone()]);
+    }
+
+    const PROCESSES: &str = r#"{ "value": "Menu", "items": [
+        { "value": "Processes", "provider": "processes", "action": "next" }
+    ] }"#;
+
+    #[test]
+    fn replaces_the_process_list_after_a_signal() {
+        let mut harness = Harness::new(PROCESSES)
+            .with_output(
+                "Processes",
+                r#"{ "value": "Processes", "items": [
+                    { "id": "42", "value": "sleep", "provider": { "process": { "pid": 42 } }, "action": "next" }
+                ] }"#,
+            )
+            .with_output(
+                "sleep",
+                r#"{ "value": "sleep (42)", "items": [
+                    { "value": "Terminate", "provider": { "signal": { "pid": 42, "signal": "SIGTERM" } }, "action": "next" }
+                ] }"#,
+            )
+            .with_output(
+                "Terminate",
+                r#"{ "value": "Processes", "items": [{ "id": "7", "value": "init" }] }"#,
+            );
+
+        harness
+            .send(Message::Submit(None))
+            .send(Message::Submit(None))
+            .send(Message::Submit(None));
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.visible_values(), vec!["init"]);
+
+        harness.send(Message::HistoryBackwards);
+        assert_eq!(harness.visible_values(), vec!["Processes"]);
+    }
+
+    #[test]
+    fn notifies_about_failed_reloads() {
+        let mut harness = Harness::new(PROCESSES);
+
+        harness.send(Message::CommandsReloaded(Err(s!("commands.json: EOF"))));
+
+        assert_eq!(
+            harness.state.notification.as_deref(),
+            Some("Failed to reload the commands: commands.json: EOF")
+        );
+    }
+
+    #[test]
+    fn notifies_about_failed_jobs() {
+        let mut harness = Harness::new(PROCESSES);
+
+        harness
+            .send(Message::Submit(None))
+            .fail("Processes", "No process with pid 42");
+
+        assert_eq!(harness.exit_code, None);
+        assert_eq!(harness.state.history.depth(), 1);
+        assert_eq!(
+            harness.state.notification.as_deref(),
+            Some("No process with pid 42")
+        );
+
+        let mut harness = Harness::new(
+            r#"{ "value": "Menu", "items": [{ "value": "Docs", "shell": "docs", "action": "next" }] }"#,
+        );
+        harness
+            .send(Message::Submit(None))
+            .fail("docs", "Failed with code 1: not found");
+
+        assert_eq!(harness.exit_code, None);
+        assert_eq!(
+            harness.state.notification.as_deref(),
+            Some("Failed with code 1: not found")
+        );
+    }
+
+    #[test]
+    fn runs_jobs_concurrently_and_keeps_the_first_result() {
+        let mut harness = Harness::new(
+            r#"{ "value": "Menu", "items": [
+                { "value": "Slow", "shell": "slow", "action": "next" },
+                { "value": "Fast", "shell": "fast", "action": "next" }
+            ] }"#,
+        );
+
+        harness
+            .send(Message::Submit(None))
+            .send(Message::Select(1))
+            .send(Message::Submit(None));
+        assert_eq!(harness.state.jobs.len(), 2);
+
+        harness.finish(
+            "fast",
+            r#"{ "value": "Fast", "items": [{ "value": "f" }] }"#,
+        );
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.state.jobs.len(), 1);
+        assert!(harness.state.running_items().is_empty());
+
+        harness.finish(
+            "slow",
+            r#"{ "value": "Slow", "items": [{ "value": "s" }] }"#,
+        );
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.visible_values(), vec!["f"]);
+        assert_eq!(
+            harness.state.notification.as_deref(),
+            Some("Slow finished after leaving its menu")
+        );
+    }
+
+    #[test]
+    fn keeps_results_of_left_levels_out_of_the_history() {
+        let mut harness = Harness::new(
+            r#"{ "value": "Git", "items": [
+                { "value": "Branches", "shell": "git-branches", "action": "next" }
+            ] }"#,
+        )
+        .with_output(
+            "git-branches",
+            r#"{ "value": "Branches", "items": [{ "value": "main", "shell": "git-log", "action": "next" }] }"#,
+        );
+
+        harness
+            .send(Message::Submit(None))
+            .send(Message::Submit(None));
+        assert_eq!(harness.state.running_items().len(), 1);
+
+        harness.send(Message::HistoryBackwards);
+        assert!(harness.state.running_items().is_empty());
+        harness.finish("git-log", r#"{ "value": "Log" }"#);
+
+        assert_eq!(harness.state.history.depth(), 1);
+        assert!(harness.state.jobs.is_empty());
+        assert!(harness.state.notification.is_some());
+    }
+
+    #[test]
+    fn tracks_levels_by_id_instead_of_content() {
+        let branches = r#"{ "value": "Branches", "items": [{ "value": "main", "shell": "git-log", "action": "next" }] }"#;
+        let mut harness = Harness::new(
+            r#"{ "value": "Git", "items": [
+                { "value": "Branches", "shell": "git-branches", "action": "next" }
+            ] }"#,
+        )
+        .with_output("git-branches", branches);
+
+        // A refreshed level is still the level the job was started from
+        harness.send(Message::Submit(None));
+        harness.outputs.clear();
+        harness
+            .send(Message::Submit(None))
+            .send(Message::RefreshLevel)
+            .finish(
+                "git-branches",
+                r#"{ "value": "Branches", "items": [
+                    { "value": "main", "shell": "git-log", "action": "next" },
+                    { "value": "dev" }
+                ] }"#,
+            );
+        assert_eq!(harness.state.running_items().len(), 1);
+        harness.finish("git-log", r#"{ "value": "Log" }"#);
+        assert_eq!(harness.state.history.depth(), 3);
+
+        // An identical level entered again is not
+        harness
+            .send(Message::HistoryBackwards)
+            .send(Message::Submit(None))
+            .send(Message::HistoryBackwards)
+            .send(Message::HistoryBackwards);
+        harness.outputs.insert(s!("git-branches"), s!(branches));
+        harness.send(Message::Submit(None));
+        assert!(harness.state.running_items().is_empty());
+        harness.finish("git-log", r#"{ "value": "Log" }"#);
+        assert_eq!(harness.state.history.depth(), 2);
+        assert!(harness.state.notification.is_some());
+    }
+
+    #[test]
+    fn shows_cached_results_and_refreshes_them_in_place() {
+        let mut harness = Harness::new(
+            r#"{ "value": "Docker", "items": [
+                { "value": "Containers", "shell": "docker-ps", "action": "next", "cache": {} }
+            ] }"#,
+        )
+        .with_output(
+            "docker-ps",
+            r#"{ "value": "Containers", "items": [{ "value": "web" }] }"#,
+        );
+
+        harness
+            .send(Message::Submit(None))
+            .send(Message::HistoryBackwards);
+        harness.outputs.clear();
+        harness.send(Message::Submit(None));
+
+        assert_eq!(harness.executed.len(), 2);
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.visible_values(), vec!["web"]);
+
+        harness.send(Message::Select(1));
+        harness.finish(
+            "docker-ps",
+            r#"{ "value": "Containers", "items": [{ "value": "db" }, { "value": "web" }] }"#,
+        );
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.visible_values(), vec!["db", "web"]);
+        assert_eq!(harness.selected_value(), Some(s!("web")));
+    }
+
+    #[test]
+    fn refreshes_levels_and_keeps_the_selected_item() {
+        let mut harness = Harness::new(
+            r#"{ "value": "System", "items": [
+                { "value": "Processes", "shell": "ps", "action": "next", "refresh_interval": 2 }
+            ] }"#,
+        )
+        .with_output(
+            "ps",
+            r#"{ "value": "Processes", "items": [
+                { "id": "1", "value": "firefox 10%" },
+                { "id": "2", "value": "code 5%" }
+            ] }"#,
+        );
+
+        assert_eq!(harness.state.refresh_interval(), None);
+        harness.send(Message::Submit(None)).send(Message::Select(1));
+        assert_eq!(
+            harness.state.refresh_interval(),
+            Some(Duration::from_secs(2))
+        );
+
+        harness.outputs.clear();
+        harness
+            .send(Message::RefreshLevel)
+            .send(Message::RefreshLevel);
+        assert_eq!(harness.running.len(), 1);
+
+        harness.finish(
+            "ps",
+            r#"{ "value": "Processes", "items": [
+                { "id": "3", "value": "top 1%" },
+                { "id": "2", "value": "code 7%" }
+            ] }"#,
+        );
+        assert_eq!(harness.state.history.depth(), 2);
+        assert_eq!(harness.visible_values(), vec!["top 1%", "code 7%"]);
+        assert_eq!(harness.selected_value(), Some(s!("code 7%")));
+        assert_eq!(
+            harness.state.refresh_interval(),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn quits_on_escape() {
+        let mut harness = Harness::new(APPS);
+
+        harness.press(KeyCode::Escape);
+
+        assert_eq!(harness.exit_code, Some(0));
+        assert!(harness.result().is_none());
+    }
+}
@@ -3,94 +3,114 @@ use iced::{
     Background, BorderRadius, Color,
 };
 
-use super::colors;
+use super::colors::with_alpha;
+use crate::core::theme::ColorPalette;
 
 pub const APP_WINDOW_BORDER_RADIUS: f32 = 10.;
 pub const DEFAULT_BORDER_RADIUS: f32 = 5.;
 
+/// A style variant drawn with the colors of the active palette.
+#[derive(Debug, Clone, Copy)]
+pub struct Themed<T> {
+    pub variant: T,
+    pub palette: ColorPalette,
+}
+
+impl<T> Themed<T> {
+    pub fn new(variant: T, palette: ColorPalette) -> Self {
+        Themed { variant, palette }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub enum ContainerStyle {
     #[default]
     Default,
     Footer,
+    // Label after the title of a row, tinted with its color
+    Badge(Color),
 }
 
-impl container::StyleSheet for ContainerStyle {
+impl container::StyleSheet for Themed<ContainerStyle> {
     type Style = iced::Theme;
 
     fn appearance(&self, _style: &Self::Style) -> container::Appearance {
-        match self {
+        let palette = &self.palette;
+        match self.variant {
             ContainerStyle::Footer => container::Appearance {
-                text_color: Some(Color {
-                    r: 1.,
-                    g: 1.,
-                    b: 1.,
-                    a: 0.35,
-                }),
+                text_color: Some(with_alpha(palette.bright.surface, 0.35)),
+                ..container::Appearance::default()
+            },
+            ContainerStyle::Badge(color) => container::Appearance {
+                text_color: Some(color),
+                background: Some(Background::Color(with_alpha(color, 0.15))),
+                border_radius: BorderRadius::from(DEFAULT_BORDER_RADIUS),
                 ..container::Appearance::default()
             },
             _ => container::Appearance {
-                background: Some(Background::Color(Color {
-                    r: 0.,
-                    g: 0.,
-                    b: 0.,
-                    a: 0.55,
-                })),
+                background: Some(Background::Color(with_alpha(palette.base.background, 0.55))),
                 border_radius: BorderRadius::from(APP_WINDOW_BORDER_RADIUS),
                 border_width: 0.5,
-                border_color: Color {
-                    r: 0.25,
-                    g: 0.25,
-                    b: 0.25,
-                    a: 0.1,
-                },
+                border_color: with_alpha(palette.normal.surface, 0.1),
                 ..container::Appearance::default()
             },
         }
     }
 }
 
-pub fn default_container_style() -> iced::theme::Container {
-    iced::theme::Container::Custom(Box::new(ContainerStyle::Default))
+pub fn default_container_style(palette: ColorPalette) -> iced::theme::Container {
+    iced::theme::Container::Custom(Box::new(Themed::new(ContainerStyle::Default, palette)))
+}
+
+pub fn footer_container_style(palette: ColorPalette) -> iced::theme::Container {
+    iced::theme::Container::Custom(Box::new(Themed::new(ContainerStyle::Footer, palette)))
 }
 
-pub fn footer_container_style() -> iced::theme::Container {
-    iced::theme::Container::Custom(Box::new(ContainerStyle::Footer))
+pub fn badge_container_style(palette: ColorPalette, color: Color) -> iced::theme::Container {
+    iced::theme::Container::Custom(Box::new(Themed::new(ContainerStyle::Badge(color), palette)))
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum Button {
     Primary,
     Focused,
     Secondary,
+    // Link to an ancestor level in the footer
+    Breadcrumb,
 }
 
-impl button::StyleSheet for Button {
+impl button::StyleSheet for Themed<Button> {
     type Style = iced::Theme;
 
     fn active(&self, _style: &Self::Style) -> button::Appearance {
+        let surface = self.palette.bright.surface;
         button::Appearance {
-            background: Some(Background::Color(match self {
-                Button::Primary => colors::BUTTON_BG_TRANSPARENT,
-                Button::Focused => colors::BUTTON_BG_SELECTED,
-                Button::Secondary => colors::BUTTON_SECONDARY,
+            background: Some(Background::Color(match self.variant {
+                Button::Primary | Button::Breadcrumb => Color::TRANSPARENT,
+                Button::Focused => with_alpha(surface, 0.05),
+                Button::Secondary => with_alpha(surface, 0.07),
             })),
             border_radius: BorderRadius::from(DEFAULT_BORDER_RADIUS),
-            text_color: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+            text_color: match self.variant {
+                Button::Breadcrumb => with_alpha(surface, 0.35),
+                _ => with_alpha(surface, 0.9),
+            },
             ..button::Appearance::default()
         }
     }
 
     fn hovered(&self, style: &Self::Style) -> button::Appearance {
+        let surface = self.palette.bright.surface;
         button::Appearance {
-            text_color: Color::WHITE,
-            background: Some(Background::Color(colors::BUTTON_SECONDARY)),
+            text_color: surface,
+            background: Some(Background::Color(with_alpha(surface, 0.07))),
             ..self.active(style)
         }
     }
 }
 
-impl From<Button> for iced::theme::Button {
-    fn from(style: Button) -> Self {
+impl From<Themed<Button>> for iced::theme::Button {
+    fn from(style: Themed<Button>) -> Self {
         iced::theme::Button::Custom(Box::new(style))
     }
 }
@@ -101,12 +121,12 @@ pub enum TextInput {
     Default,
 }
 
-impl text_input::StyleSheet for TextInput {
+impl text_input::StyleSheet for Themed<TextInput> {
     type Style = iced::Theme;
 
     fn active(&self, _style: &Self::Style) -> text_input::Appearance {
         text_input::Appearance {
-            background: Background::Color(colors::BUTTON_BG_TRANSPARENT),
+            background: Background::Color(Color::TRANSPARENT),
             border_width: 0.0,
             border_radius: 0.0.into(),
             border_color: Color::TRANSPARENT,
@@ -116,27 +136,26 @@ impl text_input::StyleSheet for TextInput {
 
     fn focused(&self, style: &Self::Style) -> text_input::Appearance {
         text_input::Appearance {
-            background: Background::Color(colors::BUTTON_BG_TRANSPARENT),
+            background: Background::Color(Color::TRANSPARENT),
             border_width: 0.0,
             ..self.active(style)
         }
     }
 
-    // Implement the missing trait items with default values
     fn placeholder_color(&self, _style: &Self::Style) -> Color {
-        Default::default()
+        with_alpha(self.palette.normal.surface, 0.5)
     }
 
     fn value_color(&self, _style: &Self::Style) -> Color {
-        Color::WHITE
+        self.palette.bright.surface
     }
 
     fn disabled_color(&self, _style: &Self::Style) -> Color {
-        Default::default()
+        self.palette.normal.surface
     }
 
     fn selection_color(&self, _style: &Self::Style) -> Color {
-        Color::WHITE
+        with_alpha(self.palette.bright.primary, 0.4)
     }
 
     fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
@@ -144,8 +163,8 @@ impl text_input::StyleSheet for TextInput {
     }
 }
 
-impl From<TextInput> for iced::theme::TextInput {
-    fn from(style: TextInput) -> Self {
+impl From<Themed<TextInput>> for iced::theme::TextInput {
+    fn from(style: Themed<TextInput>) -> Self {
         iced::theme::TextInput::Custom(Box::new(style))
     }
 }
@@ -156,17 +175,12 @@ pub enum Rule {
     Default,
 }
 
-impl rule::StyleSheet for Rule {
+impl rule::StyleSheet for Themed<Rule> {
     type Style = iced::Theme;
 
     fn appearance(&self, _style: &Self::Style) -> rule::Appearance {
         rule::Appearance {
-            color: Color {
-                r: 1.,
-                g: 1.,
-                b: 1.,
-                a: 0.01,
-            },
+            color: with_alpha(self.palette.bright.surface, 0.01),
             width: 1,
             radius: 0.0.into(),
             fill_mode: rule::FillMode::Full,
@@ -174,8 +188,8 @@ impl rule::StyleSheet for Rule {
     }
 }
 
-impl From<Rule> for iced::theme::Rule {
-    fn from(style: Rule) -> Self {
+impl From<Themed<Rule>> for iced::theme::Rule {
+    fn from(style: Themed<Rule>) -> Self {
         iced::theme::Rule::Custom(Box::new(style))
     }
 }
@@ -186,29 +200,24 @@ pub enum Svg {
     Default,
 }
 
-impl iced::widget::svg::StyleSheet for Svg {
+impl iced::widget::svg::StyleSheet for Themed<Svg> {
     type Style = iced::Theme;
 
     fn appearance(&self, _style: &Self::Style) -> svg::Appearance {
         svg::Appearance {
-            color: Some(Color {
-                r: 0.6,
-                g: 0.6,
-                b: 0.6,
-                a: 1.,
-            }),
+            color: Some(self.palette.normal.surface),
         }
     }
 }
 
-impl From<Svg> for iced::theme::Svg {
-    fn from(style: Svg) -> Self {
+impl From<Themed<Svg>> for iced::theme::Svg {
+    fn from(style: Themed<Svg>) -> Self {
         iced::theme::Svg::Custom(Box::new(style))
     }
 }
 
-pub fn get_svg_style() -> iced::theme::Svg {
-    iced::theme::Svg::Custom(Box::new(Svg::Default))
+pub fn get_svg_style(palette: ColorPalette) -> iced::theme::Svg {
+    iced::theme::Svg::Custom(Box::new(Themed::new(Svg::Default, palette)))
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -217,7 +226,7 @@ pub enum Scrollable {
     Default,
 }
 
-impl scrollable::StyleSheet for Scrollable {
+impl scrollable::StyleSheet for Themed<Scrollable> {
     type Style = iced::Theme;
 
     fn active(&self, _style: &Self::Style) -> scrollable::Scrollbar {
@@ -227,12 +236,7 @@ impl scrollable::StyleSheet for Scrollable {
             border_width: 0.0,
             border_color: Color::TRANSPARENT,
             scroller: scrollable::Scroller {
-                color: Color {
-                    r: 1.,
-                    g: 1.,
-                    b: 1.,
-                    a: 0.05,
-                },
+                color: with_alpha(self.palette.bright.surface, 0.05),
                 border_radius: BorderRadius::from(100.),
                 border_width: 0.0,
                 border_color: Color::TRANSPARENT,
@@ -245,12 +249,7 @@ impl scrollable::StyleSheet for Scrollable {
 
         let scroller = if is_mouse_over_scrollbar {
             scrollable::Scroller {
-                color: Color {
-                    r: 1.,
-                    g: 1.,
-                    b: 1.,
-                    a: 0.1,
-                },
+                color: with_alpha(self.palette.bright.surface, 0.1),
                 ..active.scroller
             }
         } else {
@@ -265,8 +264,8 @@ impl scrollable::StyleSheet for Scrollable {
     }
 }
 
-impl From<Scrollable> for iced::theme::Scrollable {
-    fn from(style: Scrollable) -> Self {
+impl From<Themed<Scrollable>> for iced::theme::Scrollable {
+    fn from(style: Themed<Scrollable>) -> Self {
         iced::theme::Scrollable::Custom(Box::new(style))
     }
 }
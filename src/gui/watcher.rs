@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{future, SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+use notify::{RecursiveMode, Watcher};
+
+use crate::core::commands::Command;
+use crate::core::loader::{self, Sources};
+
+// Editors emit several events for a single save, wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The reloaded command and where it was read from, or the error that prevented it.
+pub type Reload = Result<(Command, Sources), String>;
+
+/// Reload the commands at `path` whenever one of its `sources` changes, or a file
+/// matching one of their include patterns is created or removed.
+/// The subscription is restarted when the `sources` change.
+pub fn watch(path: PathBuf, sources: Sources) -> Subscription<Reload> {
+    struct Watch;
+
+    subscription::channel(
+        (std::any::TypeId::of::<Watch>(), sources.clone()),
+        10,
+        move |mut output| async move {
+            let (mut sender, mut receiver) = mpsc::channel(10);
+            let watcher =
+                notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    if let Ok(event) = event {
+                        let _ = sender.try_send(event);
+                    }
+                });
+
+            let _watcher = match watcher {
+                Ok(mut watcher) => {
+                    for (directory, recursive) in sources.directories() {
+                        let mode = match recursive {
+                            true => RecursiveMode::Recursive,
+                            false => RecursiveMode::NonRecursive,
+                        };
+                        if let Err(err) = watcher.watch(&directory, mode) {
+                            eprintln!("Failed to watch {}: {}", directory.display(), err);
+                        }
+                    }
+                    Some(watcher)
+                }
+                Err(err) => {
+                    eprintln!("Failed to watch the commands: {}", err);
+                    None
+                }
+            };
+
+            loop {
+                let Some(event) = receiver.next().await else {
+                    // Without a watcher there is nothing left to report
+                    future::pending::<()>().await;
+                    continue;
+                };
+                let is_change =
+                    event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove();
+                if !is_change || !event.paths.iter().any(|p| sources.affects(p)) {
+                    continue;
+                }
+
+                async_std::task::sleep(DEBOUNCE).await;
+                while receiver.try_recv().is_ok() {}
+
+                let reload =
+                    loader::load_command_with_sources(&path).map_err(|err| err.to_string());
+                let _ = output.send(reload).await;
+            }
+        },
+    )
+}
@@ -1,41 +1,274 @@
-use std::{fs, process};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 pub mod core;
 pub mod gui;
 pub mod utils;
 
-use core::{commands::Command, daemon};
+use core::check::{self, Severity};
+use core::config::{self, Config};
+use core::geometry::Anchor;
+use core::history::History;
+use core::loader;
+use core::replay;
+use core::session::{Session, SessionError};
+use core::{daemon, schema};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+    /// Path to the commands file: .json, .toml, .yaml or .yml
     #[arg(short, long)]
     json: Option<String>,
+    /// Path to the config file (keymap, ...)
+    #[arg(short, long)]
+    config: Option<String>,
+    /// Window width in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    width: Option<u32>,
+    /// Window height in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    height: Option<u32>,
+    /// Window position: center, top (the top left corner) or cursor (X11 only)
+    #[arg(long)]
+    position: Option<Anchor>,
+    /// Horizontal offset in pixels relative to the top or cursor position
+    #[arg(long, allow_hyphen_values = true)]
+    offset_x: Option<i32>,
+    /// Vertical offset in pixels relative to the top or cursor position
+    #[arg(long, allow_hyphen_values = true)]
+    offset_y: Option<i32>,
+    /// Shrink the window to show at most this many rows
+    #[arg(long)]
+    max_rows: Option<usize>,
+    /// Theme: lupin, dark, light or a path to a .toml or .json palette
+    #[arg(short, long)]
+    theme: Option<String>,
+    /// Reopen the menu where the last session with the same commands was left
+    #[arg(long)]
+    resume: bool,
+    /// Reload the commands when the file or one of its includes changes
+    #[arg(long)]
+    watch: bool,
+    /// Run the prompt without a window on a script of inputs, e.g. `type "doc"; down; enter`,
+    /// or on a file containing one, and print the resulting command as JSON
+    #[arg(long)]
+    replay: Option<String>,
+    /// Print the JSON Schema of the commands format and exit
+    #[arg(long)]
+    print_schema: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Validate a commands file and the files it includes, exits non-zero on any error
+    Check {
+        file: String,
+        /// Also exit non-zero on any warning
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+/// Print the diagnostics for `file` and the files it includes and exit, non-zero on any error
+/// or, when `strict`, on any warning.
+fn check_file(file: &str, strict: bool) -> ! {
+    let path = Path::new(file);
+    let sources = match loader::load(path) {
+        Ok((_, sources)) => sources.files,
+        // Errors in the content of a file are reported by checking it
+        Err(loader::LoaderError::Parse(source, _)) => vec![source],
+        Err(err) => {
+            println!("error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Included files are shown relative to the directory of `file`
+    let root = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let root_dir = root.parent().unwrap_or(Path::new("/"));
+    let source_name = |source: &Path| match source == root {
+        true => file.to_string(),
+        false => match source.strip_prefix(root_dir) {
+            Ok(relative) => path.with_file_name(relative).display().to_string(),
+            Err(_) => source.display().to_string(),
+        },
+    };
+
+    let mut checker = check::Checker::default();
+    let mut diagnostics = vec![];
+    for source in &sources {
+        let name = source_name(source);
+        let content = fs::read_to_string(source).unwrap_or_else(|err| {
+            println!("error: {}: {}", name, err);
+            std::process::exit(1);
+        });
+        let source_diagnostics = match source.extension().and_then(|e| e.to_str()) {
+            Some("json") | None => checker.check(&name, &content),
+            _ => match loader::parse(source, &content) {
+                Ok(value) => checker.check_value(&name, &value),
+                Err(err) => {
+                    println!("error: {}", err);
+                    std::process::exit(1);
+                }
+            },
+        };
+        diagnostics.extend(source_diagnostics.into_iter().map(|d| (name.clone(), d)));
+    }
+
+    for (name, diagnostic) in &diagnostics {
+        println!("{}: {}", name, diagnostic);
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|(_, d)| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!("{}: {} error(s), {} warning(s)", file, errors, warnings);
+
+    let failed = errors > 0 || (strict && warnings > 0);
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// Replay `script` on `history` and print the resulting command, exits non-zero when the
+/// script fails or quits the prompt.
+fn replay_script(script: &str, history: History, config: Config) -> ! {
+    let script = match Path::new(script).is_file() {
+        true => std::fs::read_to_string(script).unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", script, err);
+            std::process::exit(1);
+        }),
+        false => script.to_string(),
+    };
+    let steps = replay::parse(&script).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    });
+
+    match gui::replay::run(history, config, &steps) {
+        Ok(Some(cmd)) => {
+            println!("{}", serde_json::to_string_pretty(&cmd).unwrap());
+            std::process::exit(0);
+        }
+        Ok(None) => {
+            eprintln!("error: The script quit the prompt");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    if let Some(CliCommand::Check { file, strict }) = &cli.command {
+        check_file(file, *strict);
+    }
+
+    if cli.print_schema {
+        let schema = schema::command_schema();
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        std::process::exit(0);
+    }
+
     let config_path = cli
         .json
         .unwrap_or_else(|| shellexpand::tilde("~/.config/iced_prompt/commands.json").into_owned());
-    let json_string = fs::read_to_string(config_path).expect("{} doesn't exist");
+    let (command, sources) = match loader::load_command_with_sources(Path::new(&config_path)) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            println!("{}", err);
+            println!("Run `iced_prompt check {}` for details", config_path);
+            std::process::exit(1);
+        }
+    };
+
+    let mut config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Command line flags take precedence over the config file
+    let window = &mut config.window;
+    window.width = cli.width.unwrap_or(window.width);
+    window.height = cli.height.unwrap_or(window.height);
+    window.position = cli.position.unwrap_or(window.position);
+    window.offset_x = cli.offset_x.unwrap_or(window.offset_x);
+    window.offset_y = cli.offset_y.unwrap_or(window.offset_y);
+    window.max_rows = cli.max_rows.or(window.max_rows);
+
+    if let Some(theme) = &cli.theme {
+        config.palette = match config::load_palette(theme, Path::new(".")) {
+            Ok(palette) => palette,
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    let session_path = if cli.resume {
+        Session::default_path()
+    } else {
+        None
+    };
+    let session = session_path
+        .as_deref()
+        .and_then(|path| match Session::load(path) {
+            Ok(session) => Some(session),
+            Err(SessionError::Io(_, err)) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                eprintln!("Ignoring session: {}", err);
+                None
+            }
+        })
+        .filter(|session| session.has_root(&command));
+    let history = match session {
+        Some(session) => session.into_history(),
+        None => History::default().push(command),
+    };
+
+    if let Some(script) = &cli.replay {
+        replay_script(script, history, config);
+    }
 
-    let command: Command = serde_json::from_str(&json_string).expect("Unable to parse json");
+    let watched = cli.watch.then(|| gui::WatchedCommands {
+        path: PathBuf::from(&config_path),
+        sources,
+    });
 
-    match gui::main(command) {
-        Ok(cmd) => {
+    match gui::main(history, config, session_path, watched) {
+        Ok((cmd, context)) => {
+            let stdin = context
+                .to_stdin()
+                .map_err(|err| eprintln!("Failed to write the context: {}", err))
+                .ok();
             match cmd.action {
                 core::commands::ActionKind::Exit => {
-                    let _ = daemon::exec(cmd.command_string());
+                    let _ = daemon::exec(cmd.command_string(), stdin, cmd.cwd.as_deref());
                 }
                 core::commands::ActionKind::Print => {
-                    let output = process::Command::new("sh")
+                    let mut command = process::Command::new("sh");
+                    command
                         .args(["-c", &cmd.command_string()])
-                        .output()
-                        .expect("Failed to execute command");
+                        .stdin(stdin.map_or(process::Stdio::null(), process::Stdio::from));
+                    if let Some(cwd) = &cmd.cwd {
+                        command.current_dir(cwd);
+                    }
+                    let output = command.output().expect("Failed to execute command");
                     println!("{}", String::from_utf8_lossy(&output.stdout));
                 }
                 _ => (),
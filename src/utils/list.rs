@@ -22,18 +22,13 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 enum ListNode<T> {
+    #[default]
     Empty,
     NonEmpty(ListNodeValue<T>),
 }
 
-impl<T> Default for ListNode<T> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<T> ListNode<T> {
     fn new(item: T, next: Box<ListNode<T>>) -> Self {
         Self::NonEmpty(ListNodeValue::new(item, next))
@@ -87,6 +82,36 @@ impl<T> SinglyLinkedList<T> {
     pub fn len(&self) -> usize {
         self.size
     }
+
+    /// Iterate over references to the items, starting at the most recently pushed one.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: &self.head }
+    }
+
+    /// Pop items until at most `len` items remain.
+    pub fn truncate(&mut self, len: usize) {
+        while self.size > len {
+            self.pop();
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: &'a ListNode<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next {
+            ListNode::NonEmpty(node) => {
+                self.next = &node.next;
+                Some(&node.item)
+            }
+            ListNode::Empty => None,
+        }
+    }
 }
 
 impl<T> Iterator for SinglyLinkedList<T> {
@@ -204,6 +229,27 @@ mod tests {
         assert_eq!(vector, vec![5, 4, 3, 2, 1]);
     }
 
+    #[test]
+    fn test_iter_by_reference() {
+        let list = slist![1, 2, 3];
+        let vector: Vec<_> = list.iter().copied().collect();
+
+        assert_eq!(vector, vec![3, 2, 1]);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut list = slist![1, 2, 3, 4, 5];
+        list.truncate(2);
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.pop(), Some(2));
+
+        list.truncate(5);
+        assert_eq!(list.len(), 1);
+    }
+
     #[test]
     fn test_clone() {
         let list = slist![1, 2, 3, 4, 5];
@@ -1,2 +1,4 @@
 pub mod list;
 pub mod macros;
+#[cfg(test)]
+pub mod temp_dir;